@@ -0,0 +1,70 @@
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(version, about = "Shows rotating pages of text, images, news and weather on a screen")]
+pub struct Cli {
+    /// Folder holding pages/, providers/, data/, fonts/ and output.json
+    #[arg(long, global = true, default_value = ".")]
+    pub root: String,
+
+    /// Where downloaded images are cached, defaults to <root>/cache
+    #[arg(long, global = true)]
+    pub cache: Option<String>,
+
+    /// Output device spec such as fb:/dev/fb1, drm:/dev/dri/card0, png:frame.png or stdout,
+    /// overriding output.json
+    #[arg(long, global = true)]
+    pub device: Option<String>,
+
+    /// Canvas size as WIDTHxHEIGHT
+    #[arg(long, global = true, value_parser = parse_resolution)]
+    pub resolution: Option<(u32, u32)>,
+
+    /// Log filter such as debug or info,screen::runner=debug, overriding RUST_LOG
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Rotate through all pages on the output device (the default)
+    Run {
+        /// Jump to a page as soon as its config is saved
+        #[arg(long)]
+        follow_edits: bool
+    },
+    /// Render a single page to an image file
+    Render {
+        page: String,
+        /// PNG or PPM file, defaults to <page>.png
+        #[arg(short, long)]
+        output: Option<String>
+    },
+    /// Render every page to <dir>/<page>.png
+    RenderAll {
+        #[arg(long, default_value = ".")]
+        dir: String
+    },
+    /// Run a provider once and store its data
+    Fetch {
+        provider: String
+    },
+    /// Check every page and provider config and report the broken ones
+    Validate,
+    /// List the font names pages can use
+    ListFonts
+}
+
+fn parse_resolution(s: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("invalid resolution \"{}\", expected WIDTHxHEIGHT", s);
+    let (width, height) = s.split_once('x').ok_or_else(invalid)?;
+    let width = width.parse::<u32>().map_err(|_| invalid())?;
+    let height = height.parse::<u32>().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    Ok((width, height))
+}
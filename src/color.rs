@@ -0,0 +1,221 @@
+use crate::error::{Result, ScreenError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8
+}
+
+impl Color {
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Color{ red: r, green: g, blue: b, alpha: 255 }
+    }
+
+    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color{ red: r, green: g, blue: b, alpha: a }
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.alpha as f32 / 255.0
+    }
+
+    /// Parses CSS color syntax: `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`/`rgba()`,
+    /// `hsl()`/`hsla()` with comma or space separated arguments, and named colors.
+    pub fn parse(str: &str) -> Result<Self> {
+        let s = str.trim().to_ascii_lowercase();
+        let invalid = || ScreenError::Config(format!("invalid color \"{}\"", str));
+        if let Some(hex) = s.strip_prefix('#') {
+            return parse_hex(hex).ok_or_else(invalid);
+        }
+        if let Some((name, args)) = s.strip_suffix(')').and_then(|s| s.split_once('(')) {
+            let color = match name.trim() {
+                "rgb" | "rgba" => parse_rgb(args),
+                "hsl" | "hsla" => parse_hsl(args),
+                _ => None
+            };
+            return color.ok_or_else(invalid);
+        }
+        NAMED_COLORS.iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, rgb)| Color::new((rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8))
+            .or_else(|| (s == "transparent").then(|| Color::rgba(0, 0, 0, 0)))
+            .ok_or_else(invalid)
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match hex.len() {
+        3 => Some(Color::new(digit(0)?, digit(1)?, digit(2)?)),
+        4 => Some(Color::rgba(digit(0)?, digit(1)?, digit(2)?, digit(3)?)),
+        6 => Some(Color::new(byte(0)?, byte(2)?, byte(4)?)),
+        8 => Some(Color::rgba(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+        _ => None
+    }
+}
+
+/// Splits `1, 2, 3, 0.5` or `1 2 3 / 0.5` into the color arguments and the optional alpha.
+fn split_args(args: &str) -> Option<(Vec<&str>, Option<&str>)> {
+    let (args, slash_alpha) = match args.split_once('/') {
+        Some((args, alpha)) => (args, Some(alpha.trim())),
+        None => (args, None)
+    };
+    let mut parts: Vec<&str> = args.split(|c: char| c == ',' || c.is_whitespace()).filter(|p| !p.is_empty()).collect();
+    let alpha = match (parts.len(), slash_alpha) {
+        (3, alpha) => alpha,
+        (4, None) => parts.pop(),
+        _ => return None
+    };
+    Some((parts, alpha))
+}
+
+/// A number, or a percentage of `full`.
+fn parse_number(s: &str, full: f32) -> Option<f32> {
+    match s.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().ok().map(|p| p / 100.0 * full),
+        None => s.parse::<f32>().ok()
+    }
+}
+
+fn parse_alpha(alpha: Option<&str>) -> Option<u8> {
+    match alpha {
+        Some(alpha) => parse_number(alpha, 1.0).map(|a| (a.clamp(0.0, 1.0) * 255.0).round() as u8),
+        None => Some(255)
+    }
+}
+
+fn to_byte(v: f32) -> u8 {
+    v.clamp(0.0, 255.0).round() as u8
+}
+
+fn parse_rgb(args: &str) -> Option<Color> {
+    let (parts, alpha) = split_args(args)?;
+    let channel = |i: usize| parse_number(parts[i], 255.0).map(to_byte);
+    Some(Color::rgba(channel(0)?, channel(1)?, channel(2)?, parse_alpha(alpha)?))
+}
+
+fn parse_hsl(args: &str) -> Option<Color> {
+    let (parts, alpha) = split_args(args)?;
+    let hue = parts[0].strip_suffix("deg").unwrap_or(parts[0]).parse::<f32>().ok()?.rem_euclid(360.0);
+    let saturation = (parse_number(parts[1], 1.0)?).clamp(0.0, 1.0);
+    let lightness = (parse_number(parts[2], 1.0)?).clamp(0.0, 1.0);
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x)
+    };
+    let m = lightness - chroma / 2.0;
+    Some(Color::rgba(to_byte((r + m) * 255.0), to_byte((g + m) * 255.0), to_byte((b + m) * 255.0), parse_alpha(alpha)?))
+}
+
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff), ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff), ("beige", 0xf5f5dc), ("bisque", 0xffe4c4), ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd), ("blue", 0x0000ff), ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0), ("chartreuse", 0x7fff00), ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed), ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff), ("darkblue", 0x00008b), ("darkcyan", 0x008b8b), ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9), ("darkgreen", 0x006400), ("darkgrey", 0xa9a9a9), ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b), ("darkolivegreen", 0x556b2f), ("darkorange", 0xff8c00), ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000), ("darksalmon", 0xe9967a), ("darkseagreen", 0x8fbc8f), ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f), ("darkslategrey", 0x2f4f4f), ("darkturquoise", 0x00ced1), ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493), ("deepskyblue", 0x00bfff), ("dimgray", 0x696969), ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff), ("firebrick", 0xb22222), ("floralwhite", 0xfffaf0), ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff), ("gainsboro", 0xdcdcdc), ("ghostwhite", 0xf8f8ff), ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520), ("gray", 0x808080), ("green", 0x008000), ("greenyellow", 0xadff2f),
+    ("grey", 0x808080), ("honeydew", 0xf0fff0), ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082), ("ivory", 0xfffff0), ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5), ("lawngreen", 0x7cfc00), ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080), ("lightcyan", 0xe0ffff), ("lightgoldenrodyellow", 0xfafad2), ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90), ("lightgrey", 0xd3d3d3), ("lightpink", 0xffb6c1), ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa), ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899), ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de), ("lightyellow", 0xffffe0), ("lime", 0x00ff00), ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6), ("magenta", 0xff00ff), ("maroon", 0x800000), ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd), ("mediumorchid", 0xba55d3), ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee), ("mediumspringgreen", 0x00fa9a), ("mediumturquoise", 0x48d1cc), ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970), ("mintcream", 0xf5fffa), ("mistyrose", 0xffe4e1), ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead), ("navy", 0x000080), ("oldlace", 0xfdf5e6), ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23), ("orange", 0xffa500), ("orangered", 0xff4500), ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98), ("paleturquoise", 0xafeeee), ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5), ("peachpuff", 0xffdab9), ("peru", 0xcd853f), ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd), ("powderblue", 0xb0e0e6), ("purple", 0x800080), ("rebeccapurple", 0x663399),
+    ("red", 0xff0000), ("rosybrown", 0xbc8f8f), ("royalblue", 0x4169e1), ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072), ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57), ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d), ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb), ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090), ("slategrey", 0x708090), ("snow", 0xfffafa), ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4), ("tan", 0xd2b48c), ("teal", 0x008080), ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347), ("turquoise", 0x40e0d0), ("violet", 0xee82ee), ("wheat", 0xf5deb3),
+    ("white", 0xffffff), ("whitesmoke", 0xf5f5f5), ("yellow", 0xffff00), ("yellowgreen", 0x9acd32)
+];
+
+#[cfg(test)]
+mod tests {
+    use super::Color;
+
+    fn parse(str: &str) -> Color {
+        Color::parse(str).unwrap()
+    }
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(parse("#f80"), Color::new(0xff, 0x88, 0x00));
+        assert_eq!(parse("#f808"), Color::rgba(0xff, 0x88, 0x00, 0x88));
+        assert_eq!(parse("#1A2b3C"), Color::new(0x1a, 0x2b, 0x3c));
+        assert_eq!(parse(" #1a2b3c80 "), Color::rgba(0x1a, 0x2b, 0x3c, 0x80));
+    }
+
+    #[test]
+    fn parses_rgb() {
+        assert_eq!(parse("rgb(10, 20, 30)"), Color::new(10, 20, 30));
+        assert_eq!(parse("rgb(10 20 30)"), Color::new(10, 20, 30));
+        assert_eq!(parse("RGB(100%, 50%, 0%)"), Color::new(255, 128, 0));
+        assert_eq!(parse("rgba(10, 20, 30, 0.5)"), Color::rgba(10, 20, 30, 128));
+        assert_eq!(parse("rgb(10 20 30 / 25%)"), Color::rgba(10, 20, 30, 64));
+        assert_eq!(parse("rgb(300, -5, 30)"), Color::new(255, 0, 30));
+    }
+
+    #[test]
+    fn parses_hsl() {
+        assert_eq!(parse("hsl(0, 100%, 50%)"), Color::new(255, 0, 0));
+        assert_eq!(parse("hsl(120deg 100% 25%)"), Color::new(0, 128, 0));
+        assert_eq!(parse("hsl(240, 100%, 50%)"), Color::new(0, 0, 255));
+        assert_eq!(parse("hsl(-120, 100%, 50%)"), Color::new(0, 0, 255));
+        assert_eq!(parse("hsl(0, 0%, 100%)"), Color::new(255, 255, 255));
+        assert_eq!(parse("hsla(60, 100%, 50%, 0.2)"), Color::rgba(255, 255, 0, 51));
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(parse("rebeccapurple"), Color::new(0x66, 0x33, 0x99));
+        assert_eq!(parse("White"), Color::new(255, 255, 255));
+        assert_eq!(parse("transparent"), Color::rgba(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn keeps_alpha() {
+        for alpha in [0u8, 1, 64, 128, 254, 255] {
+            let color = parse(&format!("#102030{:02x}", alpha));
+            assert_eq!(color.alpha, alpha);
+            assert_eq!(parse(&format!("rgba(16, 32, 48, {})", color.opacity())), color);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_colors() {
+        for invalid in ["", "#", "#12", "#12345", "#1234567", "#ggg", "#ééé", "rgb(1,2)", "rgb(1,2,3,4,5)", "rgb(a,b,c)",
+                        "rgb(1,2,3", "hsl(red, 1%, 2%)", "cmyk(1,2,3,4)", "nosuchcolor", "blue green"] {
+            assert!(Color::parse(invalid).is_err(), "{} should be rejected", invalid);
+        }
+    }
+}
@@ -2,9 +2,9 @@ use std::rc::Rc;
 
 use serde_json::Value;
 
-use crate::{data::DataStore, framebuffer::Color, layout::{containerbox::{ContainerAlign, ContainerBox, ContainerDir, ContainerJustify}, LayoutItem}, utils::get_image};
+use crate::{data::DataStore, error::Result, color::Color, layout::{containerbox::{ContainerAlign, ContainerBox, ContainerDir, ContainerJustify}, errorbox::ErrorBox, LayoutItem}, utils::get_image};
 
-use super::Component;
+use super::{Component, Context};
 
 
 pub struct ContainerUnit {
@@ -19,7 +19,7 @@ pub struct ContainerUnit {
 }
 
 impl ContainerUnit {
-    pub fn new(value: &Value) -> Self {
+    pub fn new(value: &Value, context: &Context) -> Result<Self> {
         let dir = match value["dir"].as_str() {
             Some(dir_str) => { if dir_str == "row" { ContainerDir::Row } else { ContainerDir::Column } },
             None => ContainerDir::Column
@@ -55,14 +55,11 @@ impl ContainerUnit {
             None => 0
         };
         let color = match value["color"].as_str() {
-            Some(color_str) => Some(Color::from_string(color_str)),
+            Some(color) => Some(context.color(color)?),
             None => None
         };
-        let background_image_uri = match value["background_image_uri"].as_str() {
-            Some(path) => Some(path.to_string()),
-            None => None
-        };
-        ContainerUnit{ dir, align, justify, grow, pad, color, background_image_uri, children: vec![] }
+        let background_image_uri = value["background_image_uri"].as_str().map(|path| path.to_string());
+        Ok(ContainerUnit{ dir, align, justify, grow, pad, color, background_image_uri, children: vec![] })
     }
 
     pub fn add_child(&mut self, child: Box<dyn Component>) {
@@ -71,18 +68,25 @@ impl ContainerUnit {
 }
 
 impl Component for ContainerUnit {
-    fn produce(&self, data_store: &DataStore) -> Box<dyn LayoutItem> {
+    fn produce(&self, data_store: &DataStore) -> Result<Box<dyn LayoutItem>> {
         let mut container_box = ContainerBox::new(self.dir.clone(), self.align.clone(), self.justify.clone(), self.grow, self.pad, self.color.clone());
         if let Some(uri) = &self.background_image_uri {
-            let img_opt = get_image(&uri);
-            if let Some(img) = img_opt {
-                container_box.set_background_image(Rc::new(img));
+            match get_image(uri) {
+                Ok(img) => { container_box.set_background_image(Rc::new(img)); },
+                Err(e) => log::warn!("No background image: {}", e)
             }
         }
         for child in self.children.iter() {
-            let childbox = child.produce(data_store);
+            let childbox = child.produce(data_store).unwrap_or_else(|e| {
+                log::error!("Cannot produce component: {}", e);
+                Box::new(ErrorBox::new())
+            });
             container_box.add_content(childbox);
         }
-        Box::new(container_box)
+        Ok(Box::new(container_box))
+    }
+
+    fn data_dependencies(&self) -> Vec<String> {
+        self.children.iter().flat_map(|child| child.data_dependencies()).collect()
     }
 }
\ No newline at end of file
@@ -1,45 +1,55 @@
-use std::rc::Rc;
+use std::{rc::Rc, time::Duration};
 
-use chrono::{Datelike, Local, Timelike};
+use chrono::Timelike;
 use serde_json::Value;
 
-use crate::{data::DataStore, fonts::{Font, FontFactory}, framebuffer::Color, layout::{containerbox::{ContainerAlign, ContainerBox, ContainerDir, ContainerJustify}, textbox::TextBox}, utils::{get_month_name, get_weekday_name}};
+use crate::{data::DataStore, error::Result, layout::containerbox::{ContainerAlign, ContainerBox, ContainerDir, ContainerJustify}, settings::Settings, theme::{StyleDef, TextStyle}};
 
-use super::Component;
+use super::{Component, Context};
 
 
 pub struct DateTimeUnit {
     show_date: bool,
-    time_font: Font,
-    date_font: Font,
-    color: Color
+    show_seconds: bool,
+    time_style: TextStyle,
+    date_style: TextStyle,
+    settings: Rc<Settings>
 }
 
 impl DateTimeUnit {
-    pub fn new(value: &Value, font_factory: Rc<FontFactory>) -> Self {
+    pub fn new(value: &Value, context: &Context) -> Result<Self> {
         let show_date = value["showdate"].as_bool().unwrap_or(true);
-        let time_font = font_factory.get_font("DejaVuSans", 200.0).unwrap();
-        let date_font = font_factory.get_font("DejaVuSans", 40.0).unwrap();
-        let color = Color::new(240, 240, 240);
-        DateTimeUnit { 
+        let show_seconds = value["tick"].as_str() == Some("second");
+        let time_style = context.text_style(value, "time_style", "time", StyleDef::fallback(200.0, "text"))?;
+        let date_style = context.text_style(value, "date_style", "date", StyleDef::fallback(40.0, "text"))?;
+        Ok(DateTimeUnit {
             show_date,
-            time_font,
-            date_font,
-            color
-        }
+            show_seconds,
+            time_style,
+            date_style,
+            settings: context.settings.clone()
+        })
     }
 }
 
 impl Component for DateTimeUnit {
-    fn produce(&self, _data_store: &DataStore) -> Box<dyn crate::layout::LayoutItem> {
-        let now = Local::now();
-        let time_str = format!("{}:{:0>2}", now.hour(), now.minute());
-        let date_str = format!("{}, {} {}", get_weekday_name(now.weekday()), now.day(), get_month_name(now.month()));
+    fn produce(&self, _data_store: &DataStore) -> Result<Box<dyn crate::layout::LayoutItem>> {
+        let now = self.settings.now();
+        let time_str = if self.show_seconds {
+            format!("{}:{:0>2}:{:0>2}", now.hour(), now.minute(), now.second())
+        } else {
+            format!("{}:{:0>2}", now.hour(), now.minute())
+        };
+        let date_str = self.settings.format_date(&now, "%A, %-d %B");
         let mut top = ContainerBox::new(ContainerDir::Column, ContainerAlign::Center, ContainerJustify::Start, 0, 0, None);
-        top.add_content(Box::new(TextBox::new(&time_str, &self.time_font, &self.color)));
+        top.add_content(Box::new(self.time_style.text_box(&time_str)));
         if self.show_date {
-            top.add_content(Box::new(TextBox::new(&date_str, &self.date_font, &self.color)));
+            top.add_content(Box::new(self.date_style.text_box(&date_str)));
         }
-        Box::new(top)
+        Ok(Box::new(top))
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(if self.show_seconds { 1 } else { 60 }))
     }
-}
\ No newline at end of file
+}
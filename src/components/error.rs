@@ -0,0 +1,20 @@
+use crate::{data::DataStore, error::Result, layout::{errorbox::ErrorBox, LayoutItem}};
+
+use super::Component;
+
+
+/// Takes the place of a component whose config could not be loaded.
+pub struct ErrorUnit {
+}
+
+impl ErrorUnit {
+    pub fn new() -> Self {
+        ErrorUnit {  }
+    }
+}
+
+impl Component for ErrorUnit {
+    fn produce(&self, _data_store: &DataStore) -> Result<Box<dyn LayoutItem>> {
+        Ok(Box::new(ErrorBox::new()))
+    }
+}
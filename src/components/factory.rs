@@ -2,59 +2,119 @@ use std::rc::Rc;
 
 use serde_json::Value;
 
-use crate::{components::{container::ContainerUnit, image::ImageUnit, text::TextUnit, Component}, fonts::FontFactory, utils::{list_folder_configs, load_config}};
+use crate::{components::{container::ContainerUnit, image::ImageUnit, text::TextUnit, Component}, error::{Result, ScreenError}, fonts::FontFactory, settings::Settings, theme::Theme, utils::{load_config, ConfigFolder}};
 
-use super::{datetime::DateTimeUnit, news::NewsUnit, openweather::WeatherUnit, openweatherforecast::WeatherForecastUnit, randomimage::RandomImageUnit, Page};
+use super::{datetime::DateTimeUnit, error::ErrorUnit, live::LiveUnit, news::NewsUnit, openweather::WeatherUnit, openweatherforecast::WeatherForecastUnit, randomimage::RandomImageUnit, Context, Page};
 
 
 pub struct PageFactory {
-    folder: String,
+    configs: ConfigFolder,
+    themes_folder: String,
     font_factory: Rc<FontFactory>,
+    settings: Rc<Settings>
 }
 
 impl PageFactory {
-    pub fn new(f: &str) -> Self {
-        let ff = FontFactory::new();
-        PageFactory { folder: f.to_string(), font_factory: Rc::new(ff) }
+    pub fn new(f: &str, themes_folder: &str, font_factory: Rc<FontFactory>, settings: Rc<Settings>) -> Self {
+        PageFactory { configs: ConfigFolder::new(f), themes_folder: themes_folder.to_string(), font_factory, settings }
     }
 
-    pub fn list_pages(&self) -> Vec<String> {
-        list_folder_configs(&self.folder)
+    pub fn list_pages(&self) -> Result<Vec<String>> {
+        self.configs.list()
     }
 
-    pub fn load_page(&self, name: &str) -> Page {
-        let config = load_config(&self.folder, name);
+    pub fn load_page(&self, name: &str) -> Result<Page> {
+        let config = self.configs.get(name)?;
+        let context = self.context(&config).unwrap_or_else(|e| {
+            log::error!("Cannot load theme of page {}: {}", name, e);
+            Context { font_factory: self.font_factory.clone(), settings: self.settings.clone(), theme: Rc::new(Theme::default()) }
+        });
         let comp_config = &config["component"];
-        let top_component = self.recursive_comp_create(comp_config);
-        let page = Page::new(&config, top_component);
-        page
+        let top_component = self.recursive_comp_create(comp_config, &context, false)?;
+        Ok(Page::new(&config, top_component, &context))
     }
 
-    fn recursive_comp_create(&self, value: &Value) -> Box<dyn Component> {
+    /// Components of a page are created with the theme it names, or else the one from the settings.
+    fn context(&self, config: &Value) -> Result<Context> {
+        let theme = match config["theme"].as_str().or(self.settings.current_theme()) {
+            Some(theme) => Theme::new(&load_config(&self.themes_folder, theme)?)?,
+            None => Theme::default()
+        };
+        Ok(Context { font_factory: self.font_factory.clone(), settings: self.settings.clone(), theme: Rc::new(theme) })
+    }
+
+    /// From now on pages are only re-read through `reload`, which keeps the last good version.
+    pub fn keep_last_good(&mut self) {
+        self.configs.keep_last_good();
+        for name in self.list_pages().unwrap_or_default() {
+            if let Err(e) = self.reload(&name) {
+                log::error!("Page {} is broken: {}", name, e);
+            }
+        }
+    }
+
+    /// Re-reads a page config and swaps it in only if every component in it can be created.
+    pub fn reload(&mut self, name: &str) -> Result<()> {
+        match self.configs.read(name)? {
+            Some(config) => {
+                self.check(&config)?;
+                self.configs.keep(name, config);
+            },
+            None => self.configs.forget(name)
+        }
+        Ok(())
+    }
+
+    /// Fails if any component in the page can't be created.
+    pub fn validate(&self, name: &str) -> Result<()> {
+        self.check(&self.configs.get(name)?)
+    }
+
+    fn check(&self, config: &Value) -> Result<()> {
+        self.recursive_comp_create(&config["component"], &self.context(config)?, true).map(|_| ())
+    }
+
+    /// When not `strict`, a component that can't be created is logged and shown as an error placeholder.
+    fn recursive_comp_create(&self, value: &Value, context: &Context, strict: bool) -> Result<Box<dyn Component>> {
+        let comp = match self.comp_create(value, context, strict) {
+            Ok(comp) => comp,
+            Err(e) if strict => return Err(e),
+            Err(e) => {
+                log::error!("Cannot create component: {}", e);
+                return Ok(Box::new(ErrorUnit::new()));
+            }
+        };
+        match comp.refresh_interval() {
+            Some(interval) => Ok(Box::new(LiveUnit::new(comp, interval))),
+            None => Ok(comp)
+        }
+    }
+
+    fn comp_create(&self, value: &Value, context: &Context, strict: bool) -> Result<Box<dyn Component>> {
         match value["type"].as_str() {
             Some(t) => {
                 match t {
                     "container" => {
-                        let mut container = ContainerUnit::new(&value);
+                        let mut container = ContainerUnit::new(value, context)?;
                         if let Some(content_array) = value["content"].as_array() {
                             for array_item in content_array.iter() {
-                                let child = self.recursive_comp_create(&array_item.clone());
+                                let child = self.recursive_comp_create(&array_item.clone(), context, strict)?;
                                 container.add_child(child);
                             }
                         }
-                        Box::new(container)
+                        Ok(Box::new(container))
                     },
-                    "text" => Box::new(TextUnit::new(&value, self.font_factory.clone())),
-                    "image" => Box::new(ImageUnit::new(&value)),
-                    "randomimage" => Box::new(RandomImageUnit::new(&value)),
-                    "news" => Box::new(NewsUnit::new(&value, self.font_factory.clone())),
-                    "weather" => Box::new(WeatherUnit::new(&value, self.font_factory.clone())),
-                    "weatherforecast" => Box::new(WeatherForecastUnit::new(&value, self.font_factory.clone())),
-                    "datetime" => Box::new(DateTimeUnit::new(&value, self.font_factory.clone())),
-                    _ => Box::new(ContainerUnit::new(&value))
+                    "text" => Ok(Box::new(TextUnit::new(value, context)?)),
+                    "image" => Ok(Box::new(ImageUnit::new(value)?)),
+                    "randomimage" => Ok(Box::new(RandomImageUnit::new(value)?)),
+                    "news" => Ok(Box::new(NewsUnit::new(value, context)?)),
+                    "weather" => Ok(Box::new(WeatherUnit::new(value, context)?)),
+                    "weatherforecast" => Ok(Box::new(WeatherForecastUnit::new(value, context)?)),
+                    "datetime" => Ok(Box::new(DateTimeUnit::new(value, context)?)),
+                    _ => Err(ScreenError::Config(format!("unknown component type \"{}\"", t)))
                 }
             }
-            None => Box::new(ContainerUnit::new(&value))
+            None => Ok(Box::new(ContainerUnit::new(value, context)?))
         }
     }
  }
\ No newline at end of file
@@ -1,9 +1,8 @@
 use std::rc::Rc;
 
-use image::ImageReader;
 use serde_json::Value;
 
-use crate::{data::DataStore, layout::{imagebox::ImageBox, LayoutItem}};
+use crate::{data::DataStore, error::{Result, ScreenError}, layout::{imagebox::ImageBox, LayoutItem}, utils::open_image};
 
 use super::Component;
 
@@ -14,17 +13,16 @@ pub struct ImageUnit {
 }
 
 impl ImageUnit {
-    pub fn new(value: &Value) -> Self {
-        let path = value["file"].as_str().unwrap();
-        
-        ImageUnit{ path: path.to_string() }
+    pub fn new(value: &Value) -> Result<Self> {
+        let path = value["file"].as_str().ok_or_else(|| ScreenError::Config("image needs a \"file\"".to_string()))?;
+        Ok(ImageUnit{ path: path.to_string() })
     }
 }
 
 impl Component for ImageUnit {
-    fn produce(&self, _data_store: &DataStore) -> Box<dyn LayoutItem> {
-        let image = ImageReader::open(&self.path).unwrap().decode().unwrap();
+    fn produce(&self, _data_store: &DataStore) -> Result<Box<dyn LayoutItem>> {
+        let image = open_image(&self.path)?;
         let image_box = ImageBox::new(Rc::new(image));
-        Box::new(image_box)
+        Ok(Box::new(image_box))
     }
 }
\ No newline at end of file
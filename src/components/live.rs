@@ -0,0 +1,28 @@
+use std::{rc::Rc, time::Duration};
+
+use crate::{data::DataStore, error::Result, layout::{livebox::LiveBox, LayoutItem}};
+
+use super::Component;
+
+
+/// Put by the page factory around components that declare a refresh interval.
+pub struct LiveUnit {
+    inner: Rc<dyn Component>,
+    interval: Duration
+}
+
+impl LiveUnit {
+    pub fn new(inner: Box<dyn Component>, interval: Duration) -> Self {
+        LiveUnit { inner: Rc::from(inner), interval }
+    }
+}
+
+impl Component for LiveUnit {
+    fn produce(&self, data_store: &DataStore) -> Result<Box<dyn LayoutItem>> {
+        Ok(Box::new(LiveBox::new(self.inner.clone(), self.interval, self.inner.produce(data_store)?)))
+    }
+
+    fn data_dependencies(&self) -> Vec<String> {
+        self.inner.data_dependencies()
+    }
+}
@@ -1,7 +1,9 @@
 
+use std::{rc::Rc, time::Duration};
+
 use serde_json::Value;
 
-use crate::{data::DataStore, layout::LayoutItem, painter::Painter};
+use crate::{data::DataStore, error::{Result, ScreenError}, fonts::{Direction, FontFactory}, color::Color, layout::{errorbox::ErrorBox, textbox::TextAlign, LayoutItem}, painter::Painter, settings::Settings, theme::{StyleDef, TextStyle, Theme}, transition::Transition};
 pub mod factory;
 pub mod container;
 pub mod text;
@@ -11,29 +13,136 @@ pub mod news;
 pub mod openweather;
 pub mod openweatherforecast;
 pub mod datetime;
+pub mod live;
+pub mod error;
+
+/// What every component is created with: the fonts, the display-wide settings and the
+/// theme of the page.
+#[derive(Clone)]
+pub struct Context {
+    pub font_factory: Rc<FontFactory>,
+    pub settings: Rc<Settings>,
+    pub theme: Rc<Theme>
+}
+
+impl Context {
+    /// A palette color of the theme, `text`, `secondary` or `background` from the settings,
+    /// or a CSS color like `#ff8000` or `rgba(0, 0, 0, 0.5)`.
+    pub fn color(&self, name: &str) -> Result<Color> {
+        if let Some(color) = self.theme.palette_color(name) {
+            return Ok(color);
+        }
+        match name {
+            "text" => Ok(self.settings.color.clone()),
+            "secondary" => Ok(self.settings.secondary_color.clone()),
+            "background" => Ok(self.settings.background.clone()),
+            _ => Color::parse(name)
+        }
+    }
+
+    pub fn background(&self) -> Color {
+        self.theme.palette_color("background").unwrap_or_else(|| self.settings.background.clone())
+    }
+
+    /// The theme style named by `value[key]`, or the theme's `default_name` style if the
+    /// component doesn't pick one. The component's own font settings like `"font"`, `"weight"` or `"direction"` win.
+    pub fn style_def(&self, value: &Value, key: &str, default_name: &str) -> Result<StyleDef> {
+        let style = match value[key].as_str() {
+            Some(name) => self.named_style(name)?,
+            None => self.theme.style(default_name).cloned().unwrap_or_default()
+        };
+        Ok(StyleDef::font_of(value)?.or(&style))
+    }
+
+    pub fn named_style(&self, name: &str) -> Result<StyleDef> {
+        self.theme.style(name).cloned().ok_or_else(|| ScreenError::Config(format!("unknown text style \"{}\"", name)))
+    }
+
+    /// Loads the font of a style. A family that isn't installed falls back to the default font.
+    /// Characters the font lacks come from the style's fallback fonts, or those from the settings.
+    pub fn load_style(&self, def: StyleDef) -> Result<TextStyle> {
+        let family = def.font.unwrap_or_else(|| self.settings.font.clone());
+        let (weight, italic, size) = (def.weight.unwrap_or(400), def.italic.unwrap_or(false), def.size.unwrap_or(18.0));
+        let font = self.font_factory.find_font(&family, weight, italic, size).or_else(|e| {
+            if family == self.settings.font {
+                return Err(e);
+            }
+            log::warn!("{}, using {}", e, self.settings.font);
+            self.font_factory.find_font(&self.settings.font, weight, italic, size)
+        })?;
+        let fallbacks = def.fallback.as_ref().unwrap_or(&self.settings.font_fallback).iter()
+            .filter_map(|name| self.font_factory.find_font(name, weight, italic, size)
+                .map_err(|e| log::warn!("Fallback {}", e))
+                .ok())
+            .collect();
+        Ok(TextStyle {
+            font: font.with_fallbacks(fallbacks)
+                .with_shaping(Direction::parse(def.direction.as_deref().unwrap_or("auto"))?, def.language.as_deref())?,
+            color: self.color(def.color.as_deref().unwrap_or("text"))?,
+            line_height: def.line_height.unwrap_or(1.0),
+            align: TextAlign::parse(def.text_align.as_deref().unwrap_or("left"))?
+        })
+    }
+
+    /// Shorthand for `style_def` with `fallback` filling in what the theme leaves out.
+    pub fn text_style(&self, value: &Value, key: &str, default_name: &str, fallback: StyleDef) -> Result<TextStyle> {
+        self.load_style(self.style_def(value, key, default_name)?.or(&fallback))
+    }
+}
 
 pub trait Component {
-    fn produce(&self, data_store: &DataStore) -> Box<dyn LayoutItem>;
+    fn produce(&self, data_store: &DataStore) -> Result<Box<dyn LayoutItem>>;
+
+    /// Components that go stale while their page is shown, like clocks, return how often
+    /// they should be produced again.
+    fn refresh_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Names of the `DataStore` entries this component shows, so the page can be
+    /// produced again when a provider updates one of them.
+    fn data_dependencies(&self) -> Vec<String> {
+        vec![]
+    }
 }
 
 
 pub struct Page {
     top_component: Box<dyn Component>,
-    pub seconds: u32
+    pub seconds: u32,
+    pub transition: Transition,
+    pub background: Color
 }
 
 impl Page {
-    pub fn new(value: &Value, comp: Box<dyn Component>) -> Self {
-        let secs = value["seconds"].as_u64().unwrap_or(5);
-        Page{ top_component: comp, seconds: secs as u32 }
+    pub fn new(value: &Value, comp: Box<dyn Component>, context: &Context) -> Self {
+        let secs = value["seconds"].as_u64().unwrap_or(context.settings.page_seconds as u64);
+        let transition = Transition::new(&value["transition"]);
+        Page{ top_component: comp, seconds: secs as u32, transition, background: context.background() }
     }
 
-    pub fn produce(&self, data_store: &DataStore) -> Painter {
-        let mut painter = Painter::new();
-        let mut layout = self.top_component.produce(data_store);
-        layout.run_layout_top_down(1920, 1080);
+    pub fn depends_on(&self, data_name: &str) -> bool {
+        self.top_component.data_dependencies().iter().any(|name| name == data_name)
+    }
+
+    pub fn layout(&self, data_store: &DataStore, width: u32, height: u32) -> Box<dyn LayoutItem> {
+        let mut layout = self.top_component.produce(data_store).unwrap_or_else(|e| {
+            log::error!("Cannot produce page: {}", e);
+            Box::new(ErrorBox::new())
+        });
+        layout.run_layout_top_down(width, height);
         layout.run_layout_position(0, 0);
         //layout.print_layout(0);
+        layout
+    }
+
+    pub fn produce(&self, data_store: &DataStore, width: u32, height: u32) -> Painter {
+        let layout = self.layout(data_store, width, height);
+        Page::paint(layout.as_ref())
+    }
+
+    pub fn paint(layout: &dyn LayoutItem) -> Painter {
+        let mut painter = Painter::new();
         painter.add_actions(&mut layout.get_paint_actions());
         painter
     }
@@ -2,57 +2,60 @@ use std::rc::Rc;
 
 use serde_json::Value;
 
-use crate::{data::DataStore, fonts::{Font, FontFactory}, framebuffer::Color, layout::{containerbox::{ContainerAlign, ContainerBox, ContainerDir, ContainerJustify}, imagebox::ImageBox, textbox::TextBox}, models::weather::WeatherData, utils::get_image};
+use crate::{data::DataStore, error::Result, layout::{containerbox::{ContainerAlign, ContainerBox, ContainerDir, ContainerJustify}, imagebox::ImageBox}, models::weather::WeatherData, settings::Units, theme::{StyleDef, TextStyle}, utils::get_image};
 
-use super::Component;
+use super::{Component, Context};
 
 
 pub struct WeatherUnit {
     data_name: String,
-    title_font: Font,
-    general_font: Font,
-    color: Color,
+    title_style: TextStyle,
+    text_style: TextStyle,
+    units: Units
 }
 
 impl WeatherUnit {
-    pub fn new(value: &Value, font_factory: Rc<FontFactory>) -> Self {
-        let data_name = value["data"].as_str().or(Some("")).unwrap();
-        let title_font = font_factory.get_font("DejaVuSans", 80.0).unwrap();
-        let general_font = font_factory.get_font("DejaVuSans", 50.0).unwrap();
-        let color = Color::new(240, 240, 240);
-        WeatherUnit { 
+    pub fn new(value: &Value, context: &Context) -> Result<Self> {
+        let data_name = value["data"].as_str().unwrap_or("");
+        let title_style = context.text_style(value, "title_style", "weather_title", StyleDef::fallback(80.0, "text"))?;
+        let text_style = context.text_style(value, "text_style", "weather_text", StyleDef::fallback(50.0, "text"))?;
+        Ok(WeatherUnit {
             data_name: data_name.to_string(),
-            title_font,
-            general_font,
-            color
-        }
+            title_style,
+            text_style,
+            units: context.settings.units
+        })
     }
 
 
 }
 
 impl Component for WeatherUnit {
-    fn produce(&self, data_store: &DataStore) -> Box<dyn crate::layout::LayoutItem> {
+    fn produce(&self, data_store: &DataStore) -> Result<Box<dyn crate::layout::LayoutItem>> {
         let mut top = ContainerBox::new(ContainerDir::Column, ContainerAlign::Start, ContainerJustify::Start, 0, 0, None);
-        let data: WeatherData = data_store.load(&self.data_name);
+        let data: WeatherData = data_store.load(&self.data_name)?;
         let mut title_box = ContainerBox::new(ContainerDir::Row, ContainerAlign::Center,ContainerJustify::Start, 0, 0, None);
-        if let Some(icon) = get_image(&data.icon) {
+        if let Ok(icon) = get_image(&data.icon) {
             title_box.add_content(Box::new(ImageBox::new(Rc::new(icon))));
         }
-        title_box.add_content(Box::new(TextBox::new(&data.title, &self.title_font, &self.color)));
+        title_box.add_content(Box::new(self.title_style.text_box(&data.title)));
         top.add_content(Box::new(title_box));
 
         let mut line_box1 = ContainerBox::new(ContainerDir::Row, ContainerAlign::Center, ContainerJustify::Start, 0, 5, None);
-        line_box1.add_content(Box::new(TextBox::new(&format!("{}°C  ", (data.temp - 273.0) as u32), &self.general_font, &self.color)));
-        line_box1.add_content(Box::new(TextBox::new(&format!("{}kPa  ", data.pressure), &self.general_font, &self.color)));
+        line_box1.add_content(Box::new(self.text_style.text_box(&format!("{}  ", self.units.temperature(data.temp)))));
+        line_box1.add_content(Box::new(self.text_style.text_box(&format!("{}  ", self.units.pressure(data.pressure)))));
         top.add_content(Box::new(line_box1));
 
         let mut line_box2 = ContainerBox::new(ContainerDir::Row, ContainerAlign::Center, ContainerJustify::Start, 0, 5, None);
-        line_box2.add_content(Box::new(TextBox::new(&format!("{} ", get_wind_dir(data.wind_dir)), &self.general_font, &self.color)));
-        line_box2.add_content(Box::new(TextBox::new(&format!("{}km/h  ", data.wind_speed as u32), &self.general_font, &self.color)));
+        line_box2.add_content(Box::new(self.text_style.text_box(&format!("{} ", get_wind_dir(data.wind_dir)))));
+        line_box2.add_content(Box::new(self.text_style.text_box(&format!("{}  ", self.units.speed(data.wind_speed)))));
         top.add_content(Box::new(line_box2));
 
-        Box::new(top)
+        Ok(Box::new(top))
+    }
+
+    fn data_dependencies(&self) -> Vec<String> {
+        vec![self.data_name.clone()]
     }
 }
 
@@ -1,64 +1,71 @@
 use std::rc::Rc;
 
-use chrono::{Datelike, Local, TimeZone, Utc};
+use chrono::{TimeZone, Utc};
 use serde_json::Value;
 
-use crate::{data::DataStore, fonts::{Font, FontFactory}, framebuffer::Color, layout::{containerbox::{ContainerAlign, ContainerBox, ContainerDir, ContainerFixedSize, ContainerJustify}, imagebox::ImageBox, textbox::TextBox}, models::weather::WeatherForecastData, utils::{get_image, get_month_name}};
+use crate::{data::DataStore, error::Result, layout::{containerbox::{ContainerAlign, ContainerBox, ContainerDir, ContainerFixedSize, ContainerJustify}, imagebox::ImageBox}, models::weather::WeatherForecastData, settings::Settings, theme::{StyleDef, TextStyle}, utils::get_image};
 
-use super::{openweather::get_wind_dir, Component};
+use super::{openweather::get_wind_dir, Component, Context};
 
 
 pub struct WeatherForecastUnit {
     data_name: String,
-    date_font: Font,
-    title_font: Font,
-    text_font: Font,
-    color: Color,
+    date_style: TextStyle,
+    title_style: TextStyle,
+    text_style: TextStyle,
+    settings: Rc<Settings>
 }
 
 impl WeatherForecastUnit {
-    pub fn new(value: &Value, font_factory: Rc<FontFactory>) -> Self {
-        let data_name = value["data"].as_str().or(Some("")).unwrap();
-        let date_font = font_factory.get_font("DejaVuSans", 35.0).unwrap();
-        let title_font = font_factory.get_font("DejaVuSans", 40.0).unwrap();
-        let text_font = font_factory.get_font("DejaVuSans", 30.0).unwrap();
-        let color = Color::new(240, 240, 240);
-        WeatherForecastUnit { 
+    pub fn new(value: &Value, context: &Context) -> Result<Self> {
+        let data_name = value["data"].as_str().unwrap_or("");
+        let date_style = context.text_style(value, "date_style", "forecast_date", StyleDef::fallback(35.0, "text"))?;
+        let title_style = context.text_style(value, "title_style", "forecast_title", StyleDef::fallback(40.0, "text"))?;
+        let text_style = context.text_style(value, "text_style", "forecast_text", StyleDef::fallback(30.0, "text"))?;
+        Ok(WeatherForecastUnit {
             data_name: data_name.to_string(),
-            date_font,
-            title_font,
-            text_font,
-            color
-        }
+            date_style,
+            title_style,
+            text_style,
+            settings: context.settings.clone()
+        })
     }
 }
 
 impl Component for WeatherForecastUnit {
-    fn produce(&self, data_store: &DataStore) -> Box<dyn crate::layout::LayoutItem> {
+    fn produce(&self, data_store: &DataStore) -> Result<Box<dyn crate::layout::LayoutItem>> {
         let mut top = ContainerBox::new(ContainerDir::Column, ContainerAlign::Start, ContainerJustify::Start, 0, 0, None);
-        let data: WeatherForecastData = data_store.load(&self.data_name);
+        let data: WeatherForecastData = data_store.load(&self.data_name)?;
 
         for item in data.list {
             let mut line_box = ContainerBox::new(ContainerDir::Row, ContainerAlign::Center, ContainerJustify::Start, 0, 5, None);
 
             let mut date_box = ContainerBox::new_fixed_size(ContainerDir::Row, ContainerAlign::Center, ContainerJustify::Start, ContainerFixedSize{width: 230, height:40}, 0, None);
-            let date_time = Utc.timestamp_opt(item.ts, 0).unwrap().with_timezone(&Local);
-            let date_str = format!("{} {}", date_time.day(), get_month_name(date_time.month()));
-            date_box.add_content(Box::new(TextBox::new(&date_str, &self.date_font, &self.color)));
+            let Some(date_time) = Utc.timestamp_opt(item.ts, 0).single() else {
+                continue;
+            };
+            let date_time = self.settings.local_time(date_time);
+            let date_str = self.settings.format_date(&date_time, "%-d %B");
+            date_box.add_content(Box::new(self.date_style.text_box(&date_str)));
             line_box.add_content(Box::new(date_box));
 
-            if let Some(icon) = get_image(&item.icon) {
+            if let Ok(icon) = get_image(&item.icon) {
                 line_box.add_content(Box::new(ImageBox::new(Rc::new(icon))));
             }
 
             let mut line_text_col = ContainerBox::new(ContainerDir::Column, ContainerAlign::Start, ContainerJustify::Start, 0, 0, None);
-            line_text_col.add_content(Box::new(TextBox::new(&item.title, &self.title_font, &self.color)));
-            let sub_text = format!("{}°C, {} {}km/h", (item.temp - 273.0) as u32, get_wind_dir(item.wind_dir), item.wind_speed as u32);
-            line_text_col.add_content(Box::new(TextBox::new(&sub_text, &self.text_font, &self.color)));
+            line_text_col.add_content(Box::new(self.title_style.text_box(&item.title)));
+            let units = self.settings.units;
+            let sub_text = format!("{}, {} {}", units.temperature(item.temp), get_wind_dir(item.wind_dir), units.speed(item.wind_speed));
+            line_text_col.add_content(Box::new(self.text_style.text_box(&sub_text)));
             line_box.add_content(Box::new(line_text_col));
 
             top.add_content(Box::new(line_box));
         }
-        Box::new(top)
+        Ok(Box::new(top))
+    }
+
+    fn data_dependencies(&self) -> Vec<String> {
+        vec![self.data_name.clone()]
     }
 }
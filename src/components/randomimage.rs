@@ -1,10 +1,9 @@
 use std::{fs, rc::Rc};
 use rand::Rng;
 
-use image::ImageReader;
 use serde_json::Value;
 
-use crate::{data::DataStore, layout::{imagebox::ImageBox, LayoutItem}};
+use crate::{data::DataStore, error::{Result, ScreenError}, layout::{imagebox::ImageBox, LayoutItem}, utils::open_image};
 
 use super::Component;
 
@@ -14,28 +13,32 @@ pub struct RandomImageUnit {
 }
 
 impl RandomImageUnit {
-    pub fn new(value: &Value) -> Self {
-        let folder = value["folder"].as_str().unwrap();
-        RandomImageUnit{ folder: folder.to_string() }
+    pub fn new(value: &Value) -> Result<Self> {
+        let folder = value["folder"].as_str().ok_or_else(|| ScreenError::Config("randomimage needs a \"folder\"".to_string()))?;
+        Ok(RandomImageUnit{ folder: folder.to_string() })
     }
 }
 
 impl Component for RandomImageUnit {
-    fn produce(&self, _data_store: &DataStore) -> Box<dyn LayoutItem> {
-        let dir = fs::read_dir(&self.folder).unwrap();
+    fn produce(&self, _data_store: &DataStore) -> Result<Box<dyn LayoutItem>> {
+        let dir = fs::read_dir(&self.folder).map_err(|e| ScreenError::io(&self.folder, e))?;
         let mut paths: Vec<String> = vec![];
-        for dir_entry in dir {
-            let path_buf = dir_entry.unwrap().path();
-            let path = path_buf.as_os_str().to_str().unwrap();
+        for dir_entry in dir.flatten() {
+            let path_buf = dir_entry.path();
+            let Some(path) = path_buf.as_os_str().to_str() else {
+                continue;
+            };
             if path.ends_with(".jpg") || path.ends_with(".jpeg") || path.ends_with(".png") {
                 paths.push(path.to_string());
             }
         };
+        if paths.is_empty() {
+            return Err(ScreenError::image(&self.folder, "no images in folder"));
+        }
         let mut rng = rand::rng();
         let i = rng.random_range(0..paths.len());
-        let path = paths.get(i).unwrap();
-        let image = ImageReader::open(path).unwrap().decode().unwrap();
+        let image = open_image(&paths[i])?;
         let image_box = ImageBox::new(Rc::new(image));
-        Box::new(image_box)
+        Ok(Box::new(image_box))
     }
 }
\ No newline at end of file
@@ -1,38 +1,190 @@
-use std::rc::Rc;
+use serde_json::{json, Map, Value};
 
-use serde_json::Value;
+use crate::{data::DataStore, error::{Result, ScreenError}, layout::{textbox::{Fit, Overflow, Span, TextBox}, LayoutItem}, theme::{StyleDef, TextStyle}};
 
-use crate::{data::DataStore, fonts::FontFactory, framebuffer::Color, layout::{textbox::TextBox, LayoutItem}};
-
-use super::Component;
+use super::{Component, Context};
 
 
 pub struct TextUnit {
-    text: String,
-    font_factory: Rc<FontFactory>,
-    font_size: f32,
-    color: Color
+    spans: Vec<Span>,
+    style: TextStyle,
+    max_lines: Option<usize>,
+    overflow: Overflow,
+    fit: Fit,
+    min_size: Option<f32>,
+    max_size: Option<f32>
 }
 
 impl TextUnit {
-    pub fn new(value: &Value, font_factory: Rc<FontFactory>) -> Self {
-        let text = value["text"].as_str().or(Some("")).unwrap();
-        let font_size = match value["fontsize"].as_u64() {
-            Some(fontsize_num) => fontsize_num as f32,
-            None => 18.0
+    /// `"style"` picks a theme style, `"font"`, `"weight"`, `"italic"`, `"fontsize"` and `"color"`
+    /// override parts of it.
+    /// `"text"` is a string, or a list of spans like `{"text": "Breaking: ", "weight": "bold", "color": "red"}`
+    /// which take the same keys and mix in one paragraph. With `"markup": true` the string is
+    /// read as markup, see `parse_markup`.
+    /// `"max_lines": 2` limits the lines shown, `"overflow"` is `clip`, `ellipsis` or `fade` for the
+    /// last line when the text is cut off. `"text_align"` is `left`, `center`, `right` or `justify`.
+    /// `"fit"` is `shrink`, `grow` or `fill` to size the text to its box, within `"min_size"` and `"max_size"`.
+    pub fn new(value: &Value, context: &Context) -> Result<Self> {
+        let overrides = StyleDef {
+            size: value["fontsize"].as_u64().map(|size| size as f32),
+            color: value["color"].as_str().map(|color| color.to_string()),
+            ..Default::default()
         };
-        let color = match value["color"].as_str() {
-            Some(color_str) => Color::from_string(color_str),
-            None => Color::new(240, 240, 240)
+        let def = overrides.or(&context.style_def(value, "style", "text")?).or(&StyleDef::fallback(18.0, "text"));
+        let style = context.load_style(def.clone())?;
+        let spans = match &value["text"] {
+            Value::Array(spans) => spans.clone(),
+            Value::String(markup) if value["markup"].as_bool().unwrap_or(false) => parse_markup(markup)?,
+            text => vec![Value::String(text.as_str().unwrap_or("").to_string())]
         };
-        TextUnit{ text: text.to_string(), font_factory, font_size, color }
+        let spans = spans.iter()
+            .map(|span| match span {
+                Value::String(text) => Ok(style.span(text)),
+                span => Ok(span_style(span, &def, context)?.span(span["text"].as_str().unwrap_or("")))
+            })
+            .collect::<Result<Vec<Span>>>()?;
+        let overflow = Overflow::parse(value["overflow"].as_str().unwrap_or("clip"))?;
+        let max_lines = value["max_lines"].as_u64().map(|max_lines| max_lines as usize);
+        let fit = Fit::parse(value["fit"].as_str().unwrap_or("none"))?;
+        let min_size = value["min_size"].as_f64().map(|size| size as f32);
+        let max_size = value["max_size"].as_f64().map(|size| size as f32);
+        Ok(TextUnit{ spans, style, max_lines, overflow, fit, min_size, max_size })
+    }
+}
+
+/// The style of a span: its own keys over its `"style"` over the style of the whole text.
+fn span_style(value: &Value, base: &StyleDef, context: &Context) -> Result<TextStyle> {
+    let named = match value["style"].as_str() {
+        Some(name) => context.named_style(name)?,
+        None => StyleDef::default()
+    };
+    let overrides = StyleDef {
+        size: value["fontsize"].as_f64().map(|size| size as f32),
+        color: value["color"].as_str().map(|color| color.to_string()),
+        ..StyleDef::font_of(value)?
+    };
+    context.load_style(overrides.or(&named).or(base))
+}
+
+/// Turns markup into spans: `**bold**`, `*italic*`, and `{color:red}`, `{size:60}`, `{weight:300}`,
+/// `{font:DejaVu Serif}` or `{style:headline}` up to the next `{/}`. A backslash escapes the next character,
+/// so a literal star is written `\*`; emphasis left open at the end is an error.
+fn parse_markup(markup: &str) -> Result<Vec<Value>> {
+    let invalid = |reason: String| ScreenError::Config(format!("invalid markup \"{}\": {}", markup, reason));
+    let mut spans = vec![];
+    let mut text = String::new();
+    let (mut bold, mut italic) = (false, false);
+    let mut tags: Vec<(String, Value)> = vec![];
+    let flush = |text: &mut String, spans: &mut Vec<Value>, bold: bool, italic: bool, tags: &[(String, Value)]| {
+        if text.is_empty() {
+            return;
+        }
+        let mut span = Map::new();
+        span.insert("text".to_string(), json!(text));
+        if bold {
+            span.insert("weight".to_string(), json!("bold"));
+        }
+        if italic {
+            span.insert("italic".to_string(), json!(true));
+        }
+        for (key, value) in tags {
+            span.insert(key.clone(), value.clone());
+        }
+        spans.push(Value::Object(span));
+        text.clear();
+    };
+    let mut chars = markup.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.extend(chars.next()),
+            '*' => {
+                flush(&mut text, &mut spans, bold, italic, &tags);
+                if chars.next_if_eq(&'*').is_some() {
+                    bold = !bold;
+                } else {
+                    italic = !italic;
+                }
+            },
+            '{' => {
+                flush(&mut text, &mut spans, bold, italic, &tags);
+                let mut tag = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => tag.push(c),
+                        None => return Err(invalid(format!("unclosed {{{}", tag)))
+                    }
+                }
+                if tag == "/" {
+                    tags.pop().ok_or_else(|| invalid("{/} without an open tag".to_string()))?;
+                    continue;
+                }
+                let (key, value) = tag.split_once(':').ok_or_else(|| invalid(format!("expected {{key:value}}, got {{{}}}", tag)))?;
+                let value = value.trim();
+                let entry = match key.trim() {
+                    "color" => ("color", json!(value)),
+                    "size" => ("fontsize", json!(value.parse::<f64>().map_err(|_| invalid(format!("invalid size {}", value)))?)),
+                    "weight" => ("weight", json!(value)),
+                    "font" => ("font", json!(value)),
+                    "style" => ("style", json!(value)),
+                    other => return Err(invalid(format!("unknown tag {}", other)))
+                };
+                tags.push((entry.0.to_string(), entry.1));
+            },
+            c => text.push(c)
+        }
+    }
+    flush(&mut text, &mut spans, bold, italic, &tags);
+    if !tags.is_empty() {
+        return Err(invalid(format!("{} tags never closed with {{/}}", tags.len())));
+    }
+    if bold {
+        return Err(invalid("** never closed, write \\* for a star".to_string()));
+    }
+    if italic {
+        return Err(invalid("* never closed, write \\* for a star".to_string()));
     }
+    Ok(spans)
 }
 
 impl Component for TextUnit {
-    fn produce(&self, _data_store: &DataStore) -> Box<dyn LayoutItem> {
-        let font = self.font_factory.get_font("DejaVuSans", self.font_size).unwrap();
-        let textbox = TextBox::new(&self.text, &font, &self.color);
-        Box::new(textbox)
+    fn produce(&self, _data_store: &DataStore) -> Result<Box<dyn LayoutItem>> {
+        Ok(Box::new(TextBox::rich(self.spans.clone())
+            .with_line_height(self.style.line_height)
+            .with_align(self.style.align)
+            .with_overflow(self.max_lines, self.overflow)
+            .with_fit(self.fit, self.min_size, self.max_size)))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse_markup;
+
+    #[test]
+    fn markup_becomes_spans() {
+        let spans = parse_markup(r"Plain **bold *both*** {color:red}red {size:60}big{/}{/} \*star\*").unwrap();
+        assert_eq!(spans, vec![
+            json!({"text": "Plain "}),
+            json!({"text": "bold ", "weight": "bold"}),
+            json!({"text": "both", "weight": "bold", "italic": true}),
+            json!({"text": " "}),
+            json!({"text": "red ", "color": "red"}),
+            json!({"text": "big", "color": "red", "fontsize": 60.0}),
+            json!({"text": " *star*"})
+        ]);
+    }
+
+    #[test]
+    fn unbalanced_markup_is_an_error() {
+        assert!(parse_markup("5 * 3").is_err());
+        assert!(parse_markup("**bold").is_err());
+        assert!(parse_markup("{color:red}open").is_err());
+        assert!(parse_markup("close{/}").is_err());
+        assert!(parse_markup("{color:red").is_err());
+        assert!(parse_markup("{shout:yes}x{/}").is_err());
+        assert!(parse_markup(r"5 \* 3").is_ok());
+    }
+}
@@ -1,7 +1,10 @@
-use std::{fs::{self, File}, io::BufReader};
+use std::{fs::{self, File}, io::BufReader, time::{Duration, SystemTime}};
 
 use serde::de::DeserializeOwned;
 
+use crate::error::{Result, ScreenError};
+
+#[derive(Clone)]
 pub struct DataStore {
     folder: String
 }
@@ -11,16 +14,30 @@ impl DataStore {
         DataStore { folder: f.to_string() }
     }
     
-    pub fn store(&self, name: &str, json_str: &str) {
+    /// Writes next to the data and renames it into place, so readers never see half a file.
+    pub fn store(&self, name: &str, json_str: &str) -> Result<()> {
+        let path = format!("{}/{}.json", self.folder, name);
+        let tmp_path = format!("{}.tmp", path);
+        fs::write(&tmp_path, json_str).map_err(|e| ScreenError::io(&tmp_path, e))?;
+        fs::rename(&tmp_path, &path).map_err(|e| ScreenError::io(&path, e))
+    }
+
+    /// How long ago the data was last stored, if it exists.
+    pub fn age(&self, name: &str) -> Option<Duration> {
+        let path = format!("{}/{}.json", self.folder, name);
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        Some(SystemTime::now().duration_since(modified).unwrap_or_default())
+    }
+
+    pub fn invalidate(&self, name: &str) -> Result<()> {
         let path = format!("{}/{}.json", self.folder, name);
-        fs::write(path, json_str).unwrap();
+        fs::remove_file(&path).map_err(|e| ScreenError::io(&path, e))
     }
 
-    pub fn load<T: DeserializeOwned>(&self, name: &str) -> T {
+    pub fn load<T: DeserializeOwned>(&self, name: &str) -> Result<T> {
         let path = format!("{}/{}.json", self.folder, name);
-        let file = File::open(path).unwrap();
+        let file = File::open(&path).map_err(|e| ScreenError::io(&path, e))?;
         let reader = BufReader::new(file);
-        let data: T = serde_json::from_reader(reader).unwrap();
-        data
+        serde_json::from_reader(reader).map_err(|e| ScreenError::json(&path, e))
     }
 }
\ No newline at end of file
@@ -0,0 +1,39 @@
+use std::io;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ScreenError {
+    #[error("cannot read {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("invalid JSON in {path}: {source}")]
+    Json { path: String, source: serde_json::Error },
+    #[error("invalid config: {0}")]
+    Config(String),
+    #[error("font {0} not found")]
+    FontNotFound(String),
+    #[error("cannot load image {path}: {reason}")]
+    Image { path: String, reason: String },
+    #[error("request to {url} failed: {reason}")]
+    Http { url: String, reason: String }
+}
+
+impl ScreenError {
+    pub fn io(path: &str, source: io::Error) -> Self {
+        ScreenError::Io { path: path.to_string(), source }
+    }
+
+    pub fn json(path: &str, source: serde_json::Error) -> Self {
+        ScreenError::Json { path: path.to_string(), source }
+    }
+
+    pub fn image(path: &str, reason: impl ToString) -> Self {
+        ScreenError::Image { path: path.to_string(), reason: reason.to_string() }
+    }
+
+    pub fn http(url: &str, reason: impl ToString) -> Self {
+        ScreenError::Http { url: url.to_string(), reason: reason.to_string() }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ScreenError>;
@@ -1,41 +1,218 @@
-use std::{cell::RefCell, collections::HashMap, fs};
+use std::{cell::RefCell, collections::{BTreeMap, HashMap}, env, fmt, fs, path::{Path, PathBuf}, str::FromStr, sync::Arc};
 
-use rusttype::{point, Font as rtFont, PositionedGlyph, Scale};
+use rustybuzz::{ttf_parser::{self, name_id}, Language, UnicodeBuffer};
+use rusttype::{point, Font as rtFont, GlyphId, PositionedGlyph, Scale};
+use self_cell::self_cell;
+use unicode_bidi::{BidiInfo, Level};
+use unicode_properties::{GeneralCategoryGroup, UnicodeGeneralCategory};
 
+use crate::error::{Result, ScreenError};
+
+/// Which way text runs. `Auto` takes it from the first letter, like `dir="auto"` in HTML.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Direction {
+    #[default]
+    Auto,
+    Ltr,
+    Rtl
+}
+
+impl Direction {
+    pub fn parse(direction: &str) -> Result<Self> {
+        match direction {
+            "auto" => Ok(Direction::Auto),
+            "ltr" => Ok(Direction::Ltr),
+            "rtl" => Ok(Direction::Rtl),
+            other => Err(ScreenError::Config(format!("unknown direction \"{}\", expected auto, ltr or rtl", other)))
+        }
+    }
+}
+
+/// A font as parsed for drawing and for shaping, both borrowing the file data.
+struct Parsed<'a> {
+    font: rtFont<'a>,
+    shaper: rustybuzz::Face<'a>
+}
+
+self_cell!(
+    struct ParsedFont {
+        owner: Vec<u8>,
+        #[covariant]
+        dependent: Parsed,
+    }
+);
+
+/// A loaded font file, or one font of a collection, parsed once and shared between sizes.
+#[derive(Clone)]
+struct LoadedFont {
+    parsed: Arc<ParsedFont>
+}
+
+impl fmt::Debug for LoadedFont {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoadedFont").finish_non_exhaustive()
+    }
+}
+
+impl LoadedFont {
+    fn new(data: Vec<u8>, index: u32) -> Option<Self> {
+        let parsed = ParsedFont::try_new(data, |data| {
+            let font = rtFont::try_from_bytes_and_index(data, index).ok_or(())?;
+            let shaper = rustybuzz::Face::from_slice(data, index).ok_or(())?;
+            Ok::<_, ()>(Parsed { font, shaper })
+        }).ok()?;
+        Some(LoadedFont { parsed: Arc::new(parsed) })
+    }
+
+    fn font(&self) -> &rtFont<'_> {
+        &self.parsed.borrow_dependent().font
+    }
+
+    fn shaper(&self) -> &rustybuzz::Face<'_> {
+        &self.parsed.borrow_dependent().shaper
+    }
+
+    fn has_glyph(&self, c: char) -> bool {
+        self.font().glyph(c).id().0 != 0
+    }
+}
+
+/// A font at a size, with the fonts to take characters from that it doesn't have and how to
+/// shape text: the direction and the language, e.g. `ar` or `tr`, for language specific glyphs.
 #[derive(Debug, Clone)]
 pub struct Font {
-    font: rtFont<'static>,
-    size: f32
+    fonts: Vec<LoadedFont>,
+    size: f32,
+    direction: Direction,
+    language: Option<Language>
+}
+
+/// Combining marks and joiners stay in the font of the character before them.
+fn joins_previous(c: char) -> bool {
+    c.general_category_group() == GeneralCategoryGroup::Mark || c == '\u{200c}' || c == '\u{200d}'
 }
 
 impl Font {
+    fn new(font: LoadedFont, size: f32) -> Self {
+        Font { fonts: vec![font], size, direction: Direction::Auto, language: None }
+    }
+
+    /// Adds the fonts of `fallbacks`, in order, for characters this font is missing.
+    pub fn with_fallbacks(mut self, fallbacks: Vec<Font>) -> Self {
+        self.fonts.extend(fallbacks.into_iter().map(|f| f.fonts[0].clone()));
+        self
+    }
 
-    fn get_glyphs(&self, text: &str) -> Vec<PositionedGlyph> {
+    pub fn with_shaping(mut self, direction: Direction, language: Option<&str>) -> Result<Self> {
+        self.direction = direction;
+        self.language = match language {
+            Some(language) => Some(Language::from_str(language)
+                .map_err(|_| ScreenError::Config(format!("invalid language \"{}\"", language)))?),
+            None => None
+        };
+        Ok(self)
+    }
+
+    /// Splits `text` into pieces that each come from one font, the first that has the characters.
+    fn font_runs<'t>(&self, text: &'t str) -> Vec<(usize, &'t str)> {
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for (pos, c) in text.char_indices() {
+            let current = runs.last().map(|(font, _)| *font);
+            let font = match current {
+                Some(font) if c.is_whitespace() || joins_previous(c) => font,
+                _ => self.fonts.iter().position(|f| f.has_glyph(c)).or(current).unwrap_or(0)
+            };
+            if current != Some(font) {
+                runs.push((font, pos));
+            }
+        }
+        runs.iter().enumerate()
+            .map(|(i, (font, start))| (*font, &text[*start..runs.get(i + 1).map(|(_, end)| *end).unwrap_or(text.len())]))
+            .collect()
+    }
+
+    /// Lays out `text` on one line: reorders right-to-left runs for display and shapes each
+    /// run with its font, so Arabic letters join, ligatures form and pairs are kerned.
+    /// Returns the glyphs and the width of the line.
+    fn get_glyphs(&self, text: &str) -> (Vec<PositionedGlyph<'_>>, f32) {
         let scale = Scale { x: self.size * 1.0, y: self.size };
-        let v_metrics = self.font.v_metrics(scale);
-        let offset = point(0.0, v_metrics.ascent);
-        let glyphs: Vec<_> = self.font.layout(text, scale, offset).collect();
-        glyphs
+        let ascent = self.fonts[0].font().v_metrics(scale).ascent;
+        let level = match self.direction {
+            Direction::Auto => None,
+            Direction::Ltr => Some(Level::ltr()),
+            Direction::Rtl => Some(Level::rtl())
+        };
+        let bidi = BidiInfo::new(text, level);
+        let mut glyphs = Vec::new();
+        let mut caret = 0.0;
+        for paragraph in &bidi.paragraphs {
+            let (levels, runs) = bidi.visual_runs(paragraph, paragraph.range.clone());
+            for run in runs {
+                let rtl = levels[run.start].is_rtl();
+                let mut font_runs = self.font_runs(&text[run]);
+                if rtl {
+                    font_runs.reverse();
+                }
+                for (font, piece) in font_runs {
+                    caret = self.shape(&self.fonts[font], piece, rtl, scale, point(caret, ascent), &mut glyphs);
+                }
+            }
+        }
+        (glyphs, caret)
+    }
+
+    /// Shapes `text` in one font, placing the glyphs from `origin` on, and returns where the next ones go.
+    fn shape<'f>(&self, font: &'f LoadedFont, text: &str, rtl: bool, scale: Scale, origin: rusttype::Point<f32>, glyphs: &mut Vec<PositionedGlyph<'f>>) -> f32 {
+        let face = font.shaper();
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.set_direction(if rtl { rustybuzz::Direction::RightToLeft } else { rustybuzz::Direction::LeftToRight });
+        if let Some(language) = &self.language {
+            buffer.set_language(language.clone());
+        }
+        buffer.guess_segment_properties();
+        let shaped = rustybuzz::shape(face, &[], buffer);
+        // Font units to pixels, the way rusttype scales
+        let v_metrics = font.font().v_metrics_unscaled();
+        let factor = self.size / (v_metrics.ascent - v_metrics.descent);
+        let mut x = origin.x;
+        for (info, position) in shaped.glyph_infos().iter().zip(shaped.glyph_positions()) {
+            let glyph = font.font().glyph(GlyphId(info.glyph_id as u16)).scaled(scale);
+            glyphs.push(glyph.positioned(point(x + position.x_offset as f32 * factor, origin.y - position.y_offset as f32 * factor)));
+            x += position.x_advance as f32 * factor;
+        }
+        x
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    /// The same fonts at another size.
+    pub fn with_size(&self, size: f32) -> Font {
+        Font { size, ..self.clone() }
     }
 
     #[allow(dead_code)]
     pub fn get_width(&self, text: &str) -> usize {
-        let glyphs = self.get_glyphs(text);
-        let width = glyphs.iter().rev()
-            .map(|g| g.position().x as f32 + g.unpositioned().h_metrics().advance_width)
-            .next().unwrap_or(0.0).ceil() as usize;
-        width
-    }  
+        self.get_glyphs(text).1.ceil() as usize
+    }
 
     pub fn get_height(&self) -> usize {
         let scale = Scale { x: self.size * 1.0, y: self.size };
-        let v_metrics = self.font.v_metrics(scale);
+        let v_metrics = self.fonts[0].font().v_metrics(scale);
         (v_metrics.ascent - v_metrics.descent) as usize
     }
 
+    /// How far the baseline is below the top of the text `draw` paints.
+    pub fn get_ascent(&self) -> usize {
+        let scale = Scale { x: self.size * 1.0, y: self.size };
+        self.fonts[0].font().v_metrics(scale).ascent.round() as usize
+    }
+
     pub fn draw<D>(&self, text: &str, mut drawer: D)
     where D: FnMut(i32, i32, f32) {
-        let glyphs = self.get_glyphs(text);
+        let (glyphs, _) = self.get_glyphs(text);
         for g in glyphs {
             if let Some(bb) = g.pixel_bounding_box() {
                 g.draw(|x, y, v| {
@@ -45,51 +222,280 @@ impl Font {
                 })
             }
         }
-    } 
+    }
 }
 
 
+/// One installed style of a font family, e.g. `DejaVu Sans Bold Oblique`, found in a font file
+/// or at `index` in a font collection.
+#[derive(Debug, Clone)]
+struct Face {
+    name: String,
+    postscript: Option<String>,
+    family: String,
+    legacy_family: Option<String>,
+    style: String,
+    path: PathBuf,
+    index: u32,
+    weight: u16,
+    width: u16,
+    italic: bool
+}
+
 #[derive(Debug, Clone)]
 pub struct FontFactory {
-    ttf_path_map: HashMap<String, String>,
-    fonts: RefCell<HashMap<String, rtFont<'static>>>
+    faces: Vec<Face>,
+    families: HashMap<String, Vec<usize>>,
+    names: HashMap<String, usize>,
+    fonts: RefCell<HashMap<usize, LoadedFont>>
+}
+
+/// Family names are compared ignoring case and spaces, so `DejaVu Sans` finds `DejaVuSans`.
+fn family_key(family: &str) -> String {
+    family.chars().filter(|c| !c.is_whitespace()).flat_map(|c| c.to_lowercase()).collect()
+}
+
+/// Reads weight and slant from a style name like `BoldOblique` or `ExtraLight`.
+fn parse_style(style: &str) -> (u16, bool) {
+    let style = style.to_lowercase();
+    let italic = style.contains("italic") || style.contains("oblique");
+    let weights = [("thin", 100), ("extralight", 200), ("ultralight", 200), ("semilight", 350), ("light", 300),
+        ("medium", 500), ("semibold", 600), ("demibold", 600), ("extrabold", 800), ("ultrabold", 800),
+        ("bold", 700), ("black", 900), ("heavy", 900)];
+    let weight = weights.iter().find(|(name, _)| style.contains(name)).map(|(_, weight)| *weight).unwrap_or(400);
+    (weight, italic)
+}
+
+/// Parses CSS-like weights: 100 to 900 or names like `bold` and `light`.
+pub fn parse_weight(weight: &str) -> Option<u16> {
+    match weight.parse::<u16>() {
+        Ok(weight) => (1..=1000).contains(&weight).then_some(weight),
+        Err(_) => match weight.to_lowercase().as_str() {
+            "normal" | "regular" | "book" => Some(400),
+            style => Some(parse_style(style).0).filter(|w| *w != 400)
+        }
+    }
+}
+
+/// Where fonts are looked for, earlier folders winning when two hold the same font:
+/// `<root>/fonts`, the `font_dirs` from the settings, then the user's and the system's fonts.
+pub fn font_dirs(root: &str, extra: &[String]) -> Vec<PathBuf> {
+    let home = env::var("HOME").ok().map(PathBuf::from);
+    let mut dirs = vec![Path::new(root).join("fonts")];
+    for dir in extra {
+        match (dir.strip_prefix("~/"), &home) {
+            (Some(rest), Some(home)) => dirs.push(home.join(rest)),
+            _ => dirs.push(Path::new(root).join(dir))
+        }
+    }
+    if let Some(home) = &home {
+        dirs.push(home.join(".local/share/fonts"));
+        dirs.push(home.join(".fonts"));
+    }
+    dirs.push(PathBuf::from("/usr/local/share/fonts"));
+    dirs.push(PathBuf::from("/usr/share/fonts"));
+    dirs
+}
+
+/// All font files below `dir`, looking at most `depth` folders deep.
+fn find_font_files(dir: &Path, depth: u32, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            if depth > 0 {
+                find_font_files(&path, depth - 1, files);
+            }
+            continue;
+        }
+        let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+        if matches!(extension.as_deref(), Some("ttf" | "otf" | "ttc" | "otc")) {
+            files.push(path);
+        }
+    }
+}
+
+/// Which side of the wanted weight a face is searched on, lower first: heavier faces for bold,
+/// lighter ones for light text, and up to 500 then lighter for weights from 400 to 500.
+fn weight_side(wanted: u16, weight: u16) -> u8 {
+    match wanted {
+        0..400 => (weight > wanted) as u8,
+        400..=500 if (wanted..=500).contains(&weight) => 0,
+        400..=500 => if weight < wanted { 1 } else { 2 },
+        _ => (weight < wanted) as u8
+    }
+}
+
+/// The first of the name table entries `ids` that is set, preferring English.
+fn face_name(face: &ttf_parser::Face, ids: &[u16]) -> Option<String> {
+    ids.iter().find_map(|id| {
+        let names: Vec<_> = face.names().into_iter().filter(|name| name.name_id == *id).collect();
+        names.iter().find(|name| name.language_id == 0x409).or(names.first())
+            .and_then(|name| name.to_string())
+            .or_else(|| names.iter().find_map(|name| name.to_string()))
+            .filter(|name| !name.is_empty())
+    })
+}
+
+/// Reads the faces of a font file, one per font in a collection.
+fn read_faces(path: &Path) -> Vec<Face> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) => {
+            log::warn!("Cannot read font {}: {}", path.display(), e);
+            return Vec::new();
+        }
+    };
+    let count = ttf_parser::fonts_in_collection(&data).unwrap_or(1);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let mut faces = Vec::new();
+    for index in 0..count {
+        let face = match ttf_parser::Face::parse(&data, index) {
+            Ok(face) => face,
+            Err(e) => {
+                log::warn!("Cannot read font {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let (file_family, file_style) = stem.split_once('-').unwrap_or((stem, ""));
+        let family = face_name(&face, &[name_id::TYPOGRAPHIC_FAMILY, name_id::FAMILY]).unwrap_or_else(|| file_family.to_string());
+        let legacy_family = face_name(&face, &[name_id::FAMILY]).filter(|legacy| *legacy != family);
+        let style = face_name(&face, &[name_id::TYPOGRAPHIC_SUBFAMILY, name_id::SUBFAMILY]).unwrap_or_else(|| file_style.to_string());
+        let name = face_name(&face, &[name_id::FULL_NAME]).unwrap_or_else(|| format!("{} {}", family, style).trim().to_string());
+        let (style_weight, style_italic) = parse_style(&style);
+        let weight = if face.tables().os2.is_some() { face.weight().to_number() } else { style_weight };
+        faces.push(Face {
+            name,
+            postscript: face_name(&face, &[name_id::POST_SCRIPT_NAME]),
+            family,
+            legacy_family,
+            style,
+            path: path.to_path_buf(),
+            index,
+            weight,
+            width: face.width().to_number(),
+            italic: face.is_italic() || face.is_oblique() || style_italic
+        });
+    }
+    faces
 }
 
 
 impl FontFactory {
-    pub fn new() -> Self {
-        let mut ttf_path_map: HashMap<String, String> = HashMap::new();
-        let paths = fs::read_dir("/usr/share/fonts/truetype").unwrap();
-        for subpath in paths {
-            let subpath_buf = subpath.unwrap().path();
-            let ttfpaths = fs::read_dir(subpath_buf).unwrap();
-            for ttfpath in ttfpaths {
-                let ttfpath_buf = ttfpath.unwrap().path();
-                let file_name = ttfpath_buf.file_name().unwrap().to_str().unwrap();                
-                if file_name.ends_with(".ttf") {
-                    let name = &file_name[0..file_name.len() - 4];
-                    let fullpath = ttfpath_buf.to_str().unwrap();
-                    //println!("{}", name);
-                    ttf_path_map.insert(name.to_string(), fullpath.to_string() );
+    /// Indexes the fonts below `dirs` by the family, style and full name stored in them.
+    /// The PostScript name and, for single fonts, the file name such as `DejaVuSans-Bold` work as well.
+    pub fn new(dirs: &[PathBuf]) -> Self {
+        let mut factory = FontFactory { faces: Vec::new(), families: HashMap::new(), names: HashMap::new(), fonts: RefCell::new(HashMap::new()) };
+        for dir in dirs {
+            if !dir.is_dir() {
+                log::debug!("No fonts in {}", dir.display());
+                continue;
+            }
+            let mut files = Vec::new();
+            find_font_files(dir, 8, &mut files);
+            for path in files {
+                for face in read_faces(&path) {
+                    factory.add(face);
                 }
             }
         }
-        FontFactory { ttf_path_map, fonts: RefCell::new(HashMap::new()) }
+        log::debug!("Found {} fonts in {} families", factory.faces.len(), factory.families.len());
+        factory
+    }
+
+    fn add(&mut self, face: Face) {
+        let name_key = family_key(&face.name);
+        if self.names.contains_key(&name_key) {
+            log::debug!("Font {} in {} is already installed", face.name, face.path.display());
+            return;
+        }
+        let id = self.faces.len();
+        let mut keys = vec![name_key];
+        keys.extend(face.postscript.as_deref().map(family_key));
+        let is_collection = face.path.extension().is_some_and(|e| e.eq_ignore_ascii_case("ttc") || e.eq_ignore_ascii_case("otc"));
+        if let (false, Some(stem)) = (is_collection, face.path.file_stem().and_then(|s| s.to_str())) {
+            keys.push(family_key(stem));
+        }
+        for key in keys {
+            self.names.entry(key).or_insert(id);
+        }
+        self.families.entry(family_key(&face.family)).or_default().push(id);
+        // Older names like `DejaVu Sans Condensed` pick among their own styles
+        if let Some(legacy_family) = &face.legacy_family {
+            self.families.entry(family_key(legacy_family)).or_default().push(id);
+        }
+        self.faces.push(face);
     }
 
-    pub fn get_font(&self, name: &str, size: f32) -> Option<Font> {
-        if let Some(rt_font) = self.fonts.borrow().get(name) {
-            return Some(Font { font: rt_font.clone(), size: size });
+    /// Every installed family with its styles, e.g. `DejaVu Sans: Bold, Book, ExtraLight`.
+    pub fn font_names(&self) -> Vec<String> {
+        let mut families: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for face in &self.faces {
+            families.entry(&face.family).or_default().push(&face.style);
         }
+        families.into_iter()
+            .map(|(family, mut styles)| {
+                styles.sort();
+                format!("{}: {}", family, styles.join(", "))
+            })
+            .collect()
+    }
 
-        if !self.ttf_path_map.contains_key(name) {
-            return None;
+    /// Picks the installed style of `family` closest to `weight` and `italic`: the right slant
+    /// first, then the weight as CSS does (see `weight_side`), then the width closest to normal.
+    /// The full name of a style such as `DejaVu Sans Bold` or its file name works as well.
+    pub fn find_font(&self, family: &str, weight: u16, italic: bool, size: f32) -> Result<Font> {
+        let key = family_key(family);
+        let Some(ids) = self.families.get(&key) else {
+            let id = self.names.get(&key).ok_or_else(|| ScreenError::FontNotFound(family.to_string()))?;
+            return self.load(*id, size);
+        };
+        let id = ids.iter()
+            .min_by_key(|id| {
+                let face = &self.faces[**id];
+                (face.italic != italic, weight_side(weight, face.weight), face.weight.abs_diff(weight), face.width.abs_diff(5))
+            })
+            .ok_or_else(|| ScreenError::FontNotFound(family.to_string()))?;
+        let face = &self.faces[*id];
+        if face.weight != weight || face.italic != italic {
+            log::debug!("No {} {}{} installed, using {}", family, weight, if italic { " italic" } else { "" }, face.name);
         }
-        
-        let path = self.ttf_path_map.get(name).unwrap();
-        let font_data = std::fs::read(path).unwrap();
-        let rt_font = rtFont::try_from_vec(font_data).unwrap();
-        self.fonts.borrow_mut().insert(name.to_string(), rt_font.clone());
-        return Some(Font { font: rt_font.clone(), size: size });
+        self.load(*id, size)
     }
-}
\ No newline at end of file
+
+    fn load(&self, id: usize, size: f32) -> Result<Font> {
+        if let Some(font) = self.fonts.borrow().get(&id) {
+            return Ok(Font::new(font.clone(), size));
+        }
+
+        let face = &self.faces[id];
+        let font_data = fs::read(&face.path).map_err(|e| ScreenError::io(&face.path.to_string_lossy(), e))?;
+        let font = LoadedFont::new(font_data, face.index).ok_or_else(|| ScreenError::FontNotFound(face.name.clone()))?;
+        self.fonts.borrow_mut().insert(id, font.clone());
+        Ok(Font::new(font, size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::weight_side;
+
+    /// The weight CSS would pick for `wanted` out of `available`.
+    fn closest(wanted: u16, available: &[u16]) -> u16 {
+        *available.iter().min_by_key(|weight| (weight_side(wanted, **weight), weight.abs_diff(wanted))).unwrap()
+    }
+
+    #[test]
+    fn weights_match_like_css() {
+        assert_eq!(closest(600, &[400, 900]), 900);
+        assert_eq!(closest(700, &[300, 400]), 400);
+        assert_eq!(closest(300, &[100, 400]), 100);
+        assert_eq!(closest(300, &[400, 500]), 400);
+        assert_eq!(closest(400, &[300, 500]), 500);
+        assert_eq!(closest(400, &[300, 600]), 300);
+        assert_eq!(closest(500, &[400, 600]), 400);
+    }
+}
@@ -1,108 +1,96 @@
-use std::fs::{self, File};
+use crate::{color::Color, output::dirty::Rect};
 
-use png_encode_mini::write_rgba_from_u32;
-
-
-#[derive(Debug, Clone)]
-pub struct Color {
-    pub red: u8,
-    pub green: u8,
-    pub blue: u8
-}
-
-impl Color {
-    pub fn new(r: u8, g: u8, b: u8) -> Self {
-        Color{ red: r, green: g, blue: b }
-    }
-
-    pub fn from_string(str: &str) -> Self {
-        if str.starts_with("#") && str.len() == 7 {
-            let red = u8::from_str_radix(&str[1..3], 16).unwrap();
-            let green = u8::from_str_radix(&str[3..5], 16).unwrap();
-            let blue = u8::from_str_radix(&str[5..7], 16).unwrap();
-            Color {red, green, blue}
-        } else {
-            Color {red: 0, green: 0, blue: 0}
-        }
-    }
-}
 
+/// RGBA8888 working canvas that everything is painted on. Conversion to the
+/// panel's pixel format happens once per frame when a sink shows it.
 pub struct FrameBuffer {
     buf: Vec<u8>,
     width: u32,
     height: u32,
-    bpp: u8,
+    clip: Option<Rect>,
+    background: Color
 }
 
 impl FrameBuffer {
-    pub fn new() -> Self {
-        let width: u32 = 1920;
-        let height: u32 = 1080;
-        let bpp: u8 = 16;
-        let size: usize = (width * height * ((bpp / 8) as u32)) as usize;
-        let buf:Vec<u8> = vec![0; size];
-        FrameBuffer {buf, width, height, bpp}
+    pub fn new(width: u32, height: u32) -> Self {
+        let buf:Vec<u8> = vec![0; (width * height * 4) as usize];
+        let mut fb = FrameBuffer {buf, width, height, clip: None, background: Color::new(0, 0, 0)};
+        fb.clear();
+        fb
     }
 
-    #[allow(dead_code)]
-    pub fn send(&self) {
-        match fs::write("/dev/fb0", &self.buf) {
-            Ok(_) => {},
-            Err(error) => {
-                println!("{}", error);
-            },
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Restricts `poke` and `clear` to the given region, used to repaint part of a page.
+    pub fn set_clip(&mut self, clip: Option<Rect>) {
+        self.clip = clip;
+    }
+
+    fn in_clip(&self, x: i32, y: i32) -> bool {
+        match &self.clip {
+            Some(r) => x >= r.x as i32 && y >= r.y as i32 && x < (r.x + r.width) as i32 && y < (r.y + r.height) as i32,
+            None => true
         }
     }
-    
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.buf
+    }
+
+    pub fn get_rgb(&self, x: u32, y: u32) -> (u8, u8, u8) {
+        let i = ((x + (self.width * y)) * 4) as usize;
+        (self.buf[i], self.buf[i + 1], self.buf[i + 2])
+    }
+
+    pub fn set_rgb(&mut self, x: u32, y: u32, r: u8, g: u8, b: u8) {
+        let i = ((x + (self.width * y)) * 4) as usize;
+        self.buf[i..i + 4].copy_from_slice(&[r, g, b, 255]);
+    }
+
     pub fn poke(&mut self, x: i32, y: i32, r: u8, g: u8, b: u8, o: f32) {
         let w: i32 = self.width as i32;
         let h: i32 = self.height as i32;
-        if x >= 0 && x < w && y >= 0 && y < h {
-            let i: usize = (x + (w * y)) as usize;
-            if self.bpp == 16 {
-                let e1 = self.buf[(i * 2) + 1];
-                let e2 = self.buf[(i * 2) + 0];
-                let erf = ((e1 & 248) as f32) / 255.0;
-                let egf = ((((e1 << 3) | (e2 >> 5)) << 2) as f32) / 255.0;
-                let ebf = (((e2 << 3) & 248) as f32) / 255.0;
-                let trf = (r as f32) / 255.0;
-                let tgf = (g as f32) / 255.0;
-                let tbf = (b as f32) / 255.0;
-                let nrf = erf + (o * (trf - erf));
-                let ngf = egf + (o * (tgf - egf));
-                let nbf = ebf + (o * (tbf - ebf));
-                let nr = (nrf * 255.0) as u8;
-                let ng = (ngf * 255.0) as u8;
-                let nb = (nbf * 255.0) as u8;
-                self.buf[(i * 2) + 1] = (nr & 248) | ((ng & 224) >> 5);
-                self.buf[(i * 2) + 0] = ((ng & 28) << 3) | ((nb & 248) >> 3);
-            }    
+        if x >= 0 && x < w && y >= 0 && y < h && self.in_clip(x, y) {
+            let i: usize = ((x + (w * y)) * 4) as usize;
+            let o = o.clamp(0.0, 1.0);
+            for (c, t) in [r, g, b].into_iter().enumerate() {
+                let e = self.buf[i + c] as f32;
+                self.buf[i + c] = (e + (o * (t as f32 - e))).round() as u8;
+            }
+            self.buf[i + 3] = 255;
         }
     }
 
-    pub fn clear(&mut self) {
-        for i in 0..(2 * self.width * self.height) as usize {
-            self.buf[i] = 0;
-        }
+    /// The color `clear` fills with.
+    pub fn set_background(&mut self, color: Color) {
+        self.background = color;
     }
 
-    #[allow(dead_code)]
-    pub fn save_png(&self, filename: &str) {
-        let mut buf32:Vec<u32> = vec![0; (self.width * self.height)as usize];
-        for y in 0..self.height as usize {
-            for x in 0..self.width as usize {
-                let in_ptr = x + (y * self.width as usize);
-                let out_ptr = x + ((self.height as usize - 1 - y) * self.width as usize);
-                let b1 = self.buf[(in_ptr * 2) + 1];
-                let b2 = self.buf[(in_ptr * 2) + 0];
-                let r = b1 & 248;
-                let g = ((b1 << 3) | (b2 >> 5)) << 2;
-                let b = (b2 << 3) & 248;
-                let a: u8 = 255;
-                buf32[out_ptr] = ((r as u32) << 0) | ((g as u32) << 8) | ((b as u32) << 16) | ((a as u32) << 24);
+    pub fn clear(&mut self) {
+        match self.clip.clone() {
+            Some(r) => {
+                for y in r.y..(r.y + r.height).min(self.height) {
+                    for x in r.x..(r.x + r.width).min(self.width) {
+                        self.set_rgb(x, y, self.background.red, self.background.green, self.background.blue);
+                    }
+                }
+            },
+            None => {
+                let Color { red, green, blue, .. } = self.background;
+                for px in self.buf.chunks_exact_mut(4) {
+                    px.copy_from_slice(&[red, green, blue, 255]);
+                }
             }
         }
-        let mut file = File::create(filename).unwrap();
-        write_rgba_from_u32(&mut file, &buf32, self.width, self.height).unwrap();
     }
-}
\ No newline at end of file
+}
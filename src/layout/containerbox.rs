@@ -1,8 +1,8 @@
-use std::{cmp::max, rc::Rc};
+use std::{cmp::max, rc::Rc, time::SystemTime};
 
 use image::DynamicImage;
 
-use crate::{framebuffer::Color, painter::{fill::Fill, image::PaintImage, PaintAction}};
+use crate::{data::DataStore, color::Color, output::dirty::Rect, painter::{fill::Fill, image::PaintImage, PaintAction}};
 
 use super::{Layout, LayoutItem};
 
@@ -93,8 +93,8 @@ impl LayoutItem for ContainerBox {
     fn run_layout_top_down(&mut self, avail_width: u32, avail_height: u32) {
         let mut content_width = 0;
         let mut content_height = 0;
-        let inner_avail_width = if avail_width > (2*self.pad) {avail_width - (2 * self.pad)} else {0};
-        let inner_avail_height = if avail_height > (2*self.pad) {avail_height - (2 * self.pad)} else {0};
+        let inner_avail_width = avail_width.saturating_sub(2 * self.pad);
+        let inner_avail_height = avail_height.saturating_sub(2 * self.pad);
         match self.dir {
             ContainerDir::Row => {
                 let mut sum_width_grow = 0;
@@ -129,7 +129,7 @@ impl LayoutItem for ContainerBox {
                         let child_height = child.get_layout().height.unwrap_or(0);
                         content_height += child_height;
                         content_width = max(content_width, child.get_layout().width.unwrap_or(0));
-                        remain_height = if remain_height > child_height {remain_height - child_height} else {0};
+                        remain_height = remain_height.saturating_sub(child_height);
                     } else {
                         sum_height_grow += child.get_layout().height_grow;
                     }
@@ -171,8 +171,8 @@ impl LayoutItem for ContainerBox {
             content_w += child.get_layout().width.unwrap_or(0);
             content_h += child.get_layout().height.unwrap_or(0);
         }
-        let spare_w = if content_w < iw {iw - content_w} else {0};
-        let spare_h = if content_h < ih {ih - content_h} else {0};
+        let spare_w = iw.saturating_sub(content_w);
+        let spare_h = ih.saturating_sub(content_h);
         let mut ox = offsetx + self.pad;
         let mut oy = offsety + self.pad;
         match self.dir {
@@ -237,7 +237,7 @@ impl LayoutItem for ContainerBox {
         let w = self.layout.width.unwrap_or(0);
         let h = self.layout.height.unwrap_or(0);
         if let Some(color) = &self.color {
-            ret.push(Box::new(Fill::new(x, y, x+w, y+h, &color)));
+            ret.push(Box::new(Fill::new(x, y, x+w, y+h, color)));
         } else if let Some(img) = &self.background_image {
             ret.push(Box::new(PaintImage::new(x, y, w, h, img.clone())));
         }
@@ -247,4 +247,16 @@ impl LayoutItem for ContainerBox {
         }
         ret
     }
-}
\ No newline at end of file
+
+    fn next_refresh(&self) -> Option<SystemTime> {
+        self.content.iter().filter_map(|child| child.next_refresh()).min()
+    }
+
+    fn refresh_live(&mut self, data_store: &DataStore, now: SystemTime, dirty: &mut Vec<Rect>) -> bool {
+        let mut fits = true;
+        for child in self.content.iter_mut() {
+            fits &= child.refresh_live(data_store, now, dirty);
+        }
+        fits
+    }
+}
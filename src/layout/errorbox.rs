@@ -0,0 +1,55 @@
+use crate::{color::Color, painter::{fill::Fill, line::PaintLine, rect::PaintRect, PaintAction}};
+
+use super::{Layout, LayoutItem};
+
+
+/// Stands in for a component that failed to load: a dark red area crossed out
+/// corner to corner, taking whatever space its parent gives it.
+pub struct ErrorBox {
+    layout: Layout
+}
+
+impl ErrorBox {
+    pub fn new() -> Self {
+        ErrorBox { layout: Layout::grow_all(1) }
+    }
+}
+
+impl LayoutItem for ErrorBox {
+    fn get_layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    fn run_layout_top_down(&mut self, avail_width: u32, avail_height: u32) {
+        self.layout.width = Some(avail_width);
+        self.layout.height = Some(avail_height);
+    }
+
+    fn run_layout_position(&mut self, offsetx: u32, offsety: u32) {
+        self.layout.x = Some(offsetx);
+        self.layout.y = Some(offsety);
+    }
+
+    fn print_layout(&self, i: u8) {
+        let pre = (0..i).map(|_| " ").collect::<String>();
+        println!("{}-Error {}", pre, self.layout);
+    }
+
+    fn get_paint_actions(&self) -> Vec<Box<dyn PaintAction>> {
+        let x = self.layout.x.unwrap_or(0);
+        let y = self.layout.y.unwrap_or(0);
+        let w = self.layout.width.unwrap_or(0);
+        let h = self.layout.height.unwrap_or(0);
+        if w == 0 || h == 0 {
+            return vec![];
+        }
+        let background = Color::new(60, 0, 0);
+        let line = Color::new(220, 40, 40);
+        vec![
+            Box::new(Fill::new(x, y, x + w, y + h, &background)),
+            Box::new(PaintRect::new(x, y, x + w - 1, y + h - 1, &line)),
+            Box::new(PaintLine::new(x, y, x + w - 1, y + h - 1, &line)),
+            Box::new(PaintLine::new(x + w - 1, y, x, y + h - 1, &line))
+        ]
+    }
+}
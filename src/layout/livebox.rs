@@ -0,0 +1,80 @@
+use std::{rc::Rc, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use crate::{components::Component, data::DataStore, layout::errorbox::ErrorBox, output::dirty::Rect, painter::PaintAction};
+
+use super::{Layout, LayoutItem};
+
+/// Wraps the layout of a component that wants to be refreshed while its page is shown.
+/// It lays out and paints exactly like the wrapped content, and swaps that content for a
+/// freshly produced one on every tick.
+pub struct LiveBox {
+    component: Rc<dyn Component>,
+    interval: Duration,
+    next: SystemTime,
+    avail: (u32, u32),
+    content: Box<dyn LayoutItem>
+}
+
+impl LiveBox {
+    pub fn new(component: Rc<dyn Component>, interval: Duration, content: Box<dyn LayoutItem>) -> Self {
+        let next = next_tick(SystemTime::now(), interval);
+        LiveBox { component, interval, next, avail: (0, 0), content }
+    }
+}
+
+/// Ticks are aligned to whole multiples of the interval, so a minute clock changes on the minute.
+fn next_tick(now: SystemTime, interval: Duration) -> SystemTime {
+    let step = interval.as_secs().max(1);
+    let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    UNIX_EPOCH + Duration::from_secs(((secs / step) + 1) * step)
+}
+
+impl LayoutItem for LiveBox {
+    fn get_layout(&self) -> &Layout {
+        self.content.get_layout()
+    }
+
+    fn run_layout_top_down(&mut self, avail_width: u32, avail_height: u32) {
+        self.avail = (avail_width, avail_height);
+        self.content.run_layout_top_down(avail_width, avail_height);
+    }
+
+    fn run_layout_position(&mut self, offsetx: u32, offsety: u32) {
+        self.content.run_layout_position(offsetx, offsety);
+    }
+
+    fn print_layout(&self, i: u8) {
+        let pre = (0..i).map(|_| " ").collect::<String>();
+        println!("{}-Live every {}s", pre, self.interval.as_secs());
+        self.content.print_layout(i + 1);
+    }
+
+    fn get_paint_actions(&self) -> Vec<Box<dyn PaintAction>> {
+        self.content.get_paint_actions()
+    }
+
+    fn next_refresh(&self) -> Option<SystemTime> {
+        Some(self.next)
+    }
+
+    fn refresh_live(&mut self, data_store: &DataStore, now: SystemTime, dirty: &mut Vec<Rect>) -> bool {
+        if now < self.next {
+            return true;
+        }
+        self.next = next_tick(now, self.interval);
+        let old = self.content.get_layout().rect();
+        let mut content = self.component.produce(data_store).unwrap_or_else(|e| {
+            log::error!("Cannot refresh component: {}", e);
+            Box::new(ErrorBox::new())
+        });
+        content.run_layout_top_down(self.avail.0, self.avail.1);
+        self.content = content;
+        let new = self.content.get_layout().rect();
+        if new.width != old.width || new.height != old.height {
+            return false;
+        }
+        self.content.run_layout_position(old.x, old.y);
+        dirty.push(old);
+        true
+    }
+}
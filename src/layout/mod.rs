@@ -1,10 +1,12 @@
 pub mod containerbox;
 pub mod textbox;
 pub mod imagebox;
+pub mod livebox;
+pub mod errorbox;
 
-use std::fmt::Display;
+use std::{fmt::Display, time::SystemTime};
 
-use crate::painter::PaintAction;
+use crate::{data::DataStore, output::dirty::Rect, painter::PaintAction};
 
 pub struct Layout {
     x: Option<u32>,
@@ -50,6 +52,12 @@ impl Layout {
     }
 }
 
+impl Layout {
+    pub fn rect(&self) -> Rect {
+        Rect { x: self.x.unwrap_or(0), y: self.y.unwrap_or(0), width: self.width.unwrap_or(0), height: self.height.unwrap_or(0) }
+    }
+}
+
 impl Display for Layout {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let x = if let Some(x) = self.x {x as i32} else {-1};
@@ -64,6 +72,19 @@ pub trait LayoutItem {
     fn get_layout(&self) -> &Layout;
     fn run_layout_top_down(&mut self, avail_width: u32, avail_height: u32);
     fn run_layout_position(&mut self, offsetx: u32, offsety: u32);
+    #[allow(dead_code)]
     fn print_layout(&self, i: u8);
     fn get_paint_actions(&self) -> Vec<Box<dyn PaintAction>>;
+
+    /// When the earliest live part of this item wants to be produced again.
+    fn next_refresh(&self) -> Option<SystemTime> {
+        None
+    }
+
+    /// Re-produces the live parts that are due at `now` and pushes the regions to repaint
+    /// onto `dirty`. Returns false if new content no longer fits in its old place, in which
+    /// case the whole item has to be laid out and painted again.
+    fn refresh_live(&mut self, _data_store: &DataStore, _now: SystemTime, _dirty: &mut Vec<Rect>) -> bool {
+        true
+    }
 }
\ No newline at end of file
@@ -1,31 +1,431 @@
 use std::cmp::max;
 
-use crate::{fonts::Font, framebuffer::Color, painter::{text::WriteText, PaintAction}};
+use unicode_bidi::{bidi_class, BidiClass};
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{fonts::Font, color::Color, error::{Result, ScreenError}, painter::{text::WriteText, PaintAction}};
 
 use super::{Layout, LayoutItem};
 
 
+/// A run of text in one font and color.
+#[derive(Clone)]
+pub struct Span {
+    pub text: String,
+    pub font: Font,
+    pub color: Color
+}
+
+/// What the last line shows when the text has more lines than fit.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Overflow {
+    /// The lines that don't fit are left out.
+    #[default]
+    Clip,
+    /// The last line ends in `…`.
+    Ellipsis,
+    /// The last line fades out towards its end.
+    Fade
+}
+
+impl Overflow {
+    pub fn parse(overflow: &str) -> Result<Self> {
+        match overflow {
+            "clip" => Ok(Overflow::Clip),
+            "ellipsis" => Ok(Overflow::Ellipsis),
+            "fade" => Ok(Overflow::Fade),
+            other => Err(ScreenError::Config(format!("unknown overflow \"{}\", expected clip, ellipsis or fade", other)))
+        }
+    }
+}
+
+/// Where lines go within the width of the box. Justified lines, except the last of a paragraph,
+/// stretch their spaces to fill it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    Justify
+}
+
+impl TextAlign {
+    pub fn parse(align: &str) -> Result<Self> {
+        match align {
+            "left" => Ok(TextAlign::Left),
+            "center" => Ok(TextAlign::Center),
+            "right" => Ok(TextAlign::Right),
+            "justify" => Ok(TextAlign::Justify),
+            other => Err(ScreenError::Config(format!("unknown text_align \"{}\", expected left, center, right or justify", other)))
+        }
+    }
+}
+
+/// How the font size adapts to the box: `Shrink` makes it smaller until the text fits,
+/// `Grow` larger as long as it fits, `Fill` either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Fit {
+    #[default]
+    None,
+    Shrink,
+    Grow,
+    Fill
+}
+
+impl Fit {
+    pub fn parse(fit: &str) -> Result<Self> {
+        match fit {
+            "none" => Ok(Fit::None),
+            "shrink" => Ok(Fit::Shrink),
+            "grow" => Ok(Fit::Grow),
+            "fill" => Ok(Fit::Fill),
+            other => Err(ScreenError::Config(format!("unknown fit \"{}\", expected none, shrink, grow or fill", other)))
+        }
+    }
+}
+
+/// The part of a span that ended up on a line, `x` pixels from its start. While the line
+/// is filled `width` is the sum of its words and `trailing` the space at its end.
+#[derive(Clone)]
+struct Fragment {
+    span: usize,
+    text: String,
+    x: u32,
+    width: u32,
+    trailing: u32
+}
+
+#[derive(Clone)]
+struct Line {
+    fragments: Vec<Fragment>,
+    /// The span the line is in, which sizes it when it is empty.
+    span: usize,
+    width: u32,
+    ascent: u32,
+    height: u32,
+    fade: bool,
+    ends_paragraph: bool
+}
+
+impl Line {
+    fn new(span: usize) -> Self {
+        Line { fragments: vec![], span, width: 0, ascent: 0, height: 0, fade: false, ends_paragraph: false }
+    }
+}
+
+/// Text between two break opportunities, in parts where the span changes.
+struct Word<'t> {
+    parts: Vec<(usize, &'t str)>,
+    mandatory: bool
+}
+
 pub struct TextBox {
     layout: Layout,
-    text: String,
-    font: Font,
-    color: Color,
-    line_height: u32,
-    lines: Vec<String>
+    spans: Vec<Span>,
+    line_height: f32,
+    max_lines: Option<usize>,
+    overflow: Overflow,
+    align: TextAlign,
+    fit: Fit,
+    min_size: Option<f32>,
+    max_size: Option<f32>,
+    /// The font sizes of the spans before fitting.
+    sizes: Vec<f32>,
+    lines: Vec<Line>
 }
 
 impl TextBox {
     #[allow(dead_code)]
     pub fn new(text: &str, font: &Font, color: &Color) -> Self {
-        TextBox { 
-            layout: Layout::grow(1, 0), 
-            text: text.to_string(), 
-            font: font.clone(), 
-            color: color.clone(),
-            line_height: font.get_height() as u32,
+        TextBox::rich(vec![Span { text: text.to_string(), font: font.clone(), color: color.clone() }])
+    }
+
+    /// Text mixing fonts, sizes and colors, wrapped as one paragraph with the spans sharing a baseline.
+    pub fn rich(spans: Vec<Span>) -> Self {
+        TextBox {
+            layout: Layout::grow(1, 0),
+            sizes: spans.iter().map(|span| span.font.size()).collect(),
+            spans,
+            line_height: 1.0,
+            max_lines: None,
+            overflow: Overflow::Clip,
+            align: TextAlign::Left,
+            fit: Fit::None,
+            min_size: None,
+            max_size: None,
             lines: vec![]
         }
     }
+
+    /// Spaces lines `factor` times the height of their tallest font apart.
+    pub fn with_line_height(mut self, factor: f32) -> Self {
+        self.line_height = factor;
+        self
+    }
+
+    /// Shows at most `max_lines`, ending the last one as `overflow` says if there is more text.
+    /// The same happens when the lines don't fit the height, though the first line always shows.
+    pub fn with_overflow(mut self, max_lines: Option<usize>, overflow: Overflow) -> Self {
+        self.max_lines = max_lines;
+        self.overflow = overflow;
+        self
+    }
+
+    /// Aligned text other than left takes the whole width it is given.
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Scales the text to the largest size that fits the box, all spans alike, with
+    /// `min_size` and `max_size` bounding its largest font.
+    pub fn with_fit(mut self, fit: Fit, min_size: Option<f32>, max_size: Option<f32>) -> Self {
+        self.fit = fit;
+        self.min_size = min_size;
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets the size of the largest font, scaling the others along.
+    fn set_size(&mut self, size: f32) {
+        let largest = self.sizes.iter().cloned().fold(0.0, f32::max);
+        for (span, original) in self.spans.iter_mut().zip(&self.sizes) {
+            span.font = span.font.with_size(original * size / largest);
+        }
+    }
+
+    /// Whether the text fits at the current size without breaking words apart.
+    fn fits(&self, avail_width: u32, avail_height: u32) -> bool {
+        let (lines, broken) = self.break_lines(avail_width);
+        !broken
+            && lines.iter().all(|line| line.width <= avail_width)
+            && lines.iter().map(|line| line.height).sum::<u32>() <= avail_height
+            && self.max_lines.is_none_or(|max_lines| lines.len() <= max_lines)
+    }
+
+    /// Binary searches the largest whole font size that fits.
+    fn fit_size(&mut self, avail_width: u32, avail_height: u32) {
+        let largest = self.sizes.iter().cloned().fold(0.0, f32::max);
+        if largest <= 0.0 {
+            return;
+        }
+        let min_size = self.min_size.unwrap_or(8.0).max(1.0);
+        // A line can't be taller than the box, so neither can the font
+        let max_size = self.max_size.unwrap_or((avail_height as f32).min(1000.0)).max(min_size);
+        let (low, high) = match self.fit {
+            Fit::None => return,
+            Fit::Shrink => (min_size, largest.clamp(min_size, max_size)),
+            Fit::Grow => (largest.clamp(min_size, max_size), max_size),
+            Fit::Fill => (min_size, max_size)
+        };
+        self.set_size(high);
+        if self.fits(avail_width, avail_height) {
+            return;
+        }
+        // Whole sizes within the bounds, or the smallest allowed if there are none
+        let (mut low, mut high) = (low.ceil() as u32, high.floor() as u32);
+        if low > high {
+            self.set_size(min_size);
+            return;
+        }
+        while low < high {
+            let size = low + (high - low).div_ceil(2);
+            self.set_size(size as f32);
+            if self.fits(avail_width, avail_height) {
+                low = size;
+            } else {
+                high = size - 1;
+            }
+        }
+        log::debug!("Fitted text at size {}", low);
+        self.set_size(low as f32);
+    }
+
+    /// Splits the text at the places UAX #14 allows a line break. Breaks within a word
+    /// that changes span aren't allowed, `\n` forces one.
+    fn words(&self) -> Vec<Word<'_>> {
+        let text: String = self.spans.iter().map(|span| span.text.as_str()).collect();
+        let mut starts = vec![];
+        let mut offset = 0;
+        for span in &self.spans {
+            starts.push(offset);
+            offset += span.text.len();
+        }
+        let mut words = vec![];
+        let mut start = 0;
+        for (end, opportunity) in linebreaks(&text) {
+            let parts = self.spans.iter().enumerate()
+                .filter_map(|(index, span)| {
+                    let from = starts[index].max(start);
+                    let to = (starts[index] + span.text.len()).min(end);
+                    (from < to).then(|| (index, &span.text[from - starts[index]..to - starts[index]]))
+                })
+                .collect();
+            words.push(Word { parts, mandatory: opportunity == BreakOpportunity::Mandatory && end < text.len() });
+            start = end;
+        }
+        words
+    }
+
+    /// Adds text of a span to the end of a line, leaving out space at its start. Only the new
+    /// text is measured, so filling a line takes time linear in its length.
+    fn append(&self, line: &mut Line, span: usize, text: &str) {
+        line.span = span;
+        let text = if line.fragments.is_empty() { text.trim_start() } else { text };
+        if text.is_empty() {
+            return;
+        }
+        let font = &self.spans[span].font;
+        let width = font.get_width(text) as u32;
+        let trimmed = text.trim_end();
+        let trailing = if trimmed.len() == text.len() { 0 } else { width.saturating_sub(font.get_width(trimmed) as u32) };
+        match line.fragments.last_mut() {
+            Some(last) if last.span == span => {
+                last.text.push_str(text);
+                last.width += width;
+                last.trailing = if trimmed.is_empty() { last.trailing + trailing } else { trailing };
+            },
+            _ => line.fragments.push(Fragment { span, text: text.to_string(), x: 0, width, trailing })
+        }
+    }
+
+    /// Measures a fragment again as a whole, e.g. after text was taken off its end.
+    fn measure(&self, fragment: &mut Fragment) {
+        let font = &self.spans[fragment.span].font;
+        fragment.width = font.get_width(&fragment.text) as u32;
+        fragment.trailing = fragment.width.saturating_sub(font.get_width(fragment.text.trim_end()) as u32);
+    }
+
+    /// The width of a line without the space at its end.
+    fn width(&self, line: &Line) -> u32 {
+        let trailing = line.fragments.last().map(|last| last.trailing).unwrap_or(0);
+        line.fragments.iter().map(|f| f.width).sum::<u32>().saturating_sub(trailing)
+    }
+
+    /// Puts a word on the line if it fits, else on a new line. A word too long for a line of
+    /// its own is broken between characters.
+    fn place(&self, parts: &[(usize, &str)], avail_width: u32, line: &mut Line, lines: &mut Vec<Line>, broken: &mut bool) {
+        let mut trial = line.clone();
+        for (span, text) in parts {
+            self.append(&mut trial, *span, text);
+        }
+        if self.width(&trial) <= avail_width {
+            *line = trial;
+            return;
+        }
+        if !line.fragments.is_empty() {
+            let span = line.span;
+            lines.push(self.finish_line(std::mem::replace(line, Line::new(span))));
+            return self.place(parts, avail_width, line, lines, broken);
+        }
+        let single = parts.iter().map(|(_, text)| text.trim().graphemes(true).count()).sum::<usize>() <= 1;
+        if single {
+            *line = trial;
+            return;
+        }
+        *broken = true;
+        for (span, text) in parts {
+            for grapheme in text.graphemes(true) {
+                self.place(&[(*span, grapheme)], avail_width, line, lines, broken);
+            }
+        }
+    }
+
+    /// Places the fragments of a finished line side by side and sizes it by its tallest font.
+    /// The fragments are measured once more as a whole, which shaping across words can change.
+    fn finish_line(&self, mut line: Line) -> Line {
+        if let Some(last) = line.fragments.last_mut() {
+            last.text.truncate(last.text.trim_end().len());
+        }
+        line.fragments.retain(|fragment| !fragment.text.is_empty());
+        for fragment in line.fragments.iter_mut() {
+            self.measure(fragment);
+        }
+        let mut x = 0;
+        let font = &self.spans[line.span].font;
+        let (mut ascent, mut height) = if line.fragments.is_empty() { (font.get_ascent() as u32, font.get_height() as u32) } else { (0, 0) };
+        for fragment in line.fragments.iter_mut() {
+            fragment.x = x;
+            x += fragment.width;
+            let font = &self.spans[fragment.span].font;
+            ascent = max(ascent, font.get_ascent() as u32);
+            height = max(height, font.get_height() as u32);
+        }
+        line.width = x;
+        line.ascent = ascent;
+        line.height = (height as f32 * self.line_height).round().max(1.0) as u32;
+        line
+    }
+
+    /// Splits a line into its words, spread out so `spare` pixels more are between them.
+    /// Lines without spaces or with right-to-left text stay as they are.
+    fn justify(&self, line: &Line, spare: u32) -> Option<Vec<(usize, String, u32)>> {
+        let rtl = line.fragments.iter().flat_map(|f| f.text.chars()).any(|c| matches!(bidi_class(c), BidiClass::R | BidiClass::AL));
+        let gaps = line.fragments.iter().map(|f| f.text.matches(' ').count()).sum::<usize>() as u32;
+        if rtl || gaps == 0 {
+            return None;
+        }
+        let mut pieces = vec![];
+        let (mut x, mut gap) = (0, 0);
+        for fragment in &line.fragments {
+            let font = &self.spans[fragment.span].font;
+            for word in fragment.text.split_inclusive(' ') {
+                pieces.push((fragment.span, word.trim_end().to_string(), x));
+                x += font.get_width(word) as u32;
+                if word.ends_with(' ') {
+                    // The pixels that don't divide evenly go to the first gaps
+                    x += spare / gaps + u32::from(gap < spare % gaps);
+                    gap += 1;
+                }
+            }
+        }
+        Some(pieces)
+    }
+
+    /// Wraps the text into lines of at most `avail_width`, and tells whether a word had to
+    /// be broken between characters.
+    fn break_lines(&self, avail_width: u32) -> (Vec<Line>, bool) {
+        let mut lines = vec![];
+        let mut line = Line::new(0);
+        let mut broken = false;
+        for word in self.words() {
+            self.place(&word.parts, avail_width, &mut line, &mut lines, &mut broken);
+            if word.mandatory {
+                let span = line.span;
+                let line = std::mem::replace(&mut line, Line::new(span));
+                lines.push(Line { ends_paragraph: true, ..self.finish_line(line) });
+            }
+        }
+        if !line.fragments.is_empty() {
+            lines.push(Line { ends_paragraph: true, ..self.finish_line(line) });
+        }
+        (lines, broken)
+    }
+
+    /// Ends a line with `…`, taking off characters until it fits.
+    fn ellipsize(&self, mut line: Line, avail_width: u32) -> Line {
+        loop {
+            let mut trial = line.clone();
+            if let Some(last) = trial.fragments.last_mut() {
+                last.text.truncate(last.text.trim_end().len());
+                self.measure(last);
+            }
+            let span = trial.fragments.last().map(|f| f.span).unwrap_or(trial.span);
+            self.append(&mut trial, span, "\u{2026}");
+            if self.width(&trial) <= avail_width || line.fragments.is_empty() {
+                return self.finish_line(trial);
+            }
+            if let Some(last) = line.fragments.last_mut() {
+                let cut = last.text.grapheme_indices(true).next_back().map(|(i, _)| i).unwrap_or(0);
+                last.text.truncate(cut);
+                if last.text.is_empty() {
+                    line.fragments.pop();
+                }
+            }
+        }
+    }
 }
 
 impl LayoutItem for TextBox {
@@ -33,34 +433,45 @@ impl LayoutItem for TextBox {
     fn get_layout(&self) -> &Layout {
         &self.layout
     }
-    
+
     fn run_layout_top_down(&mut self, avail_width: u32, avail_height: u32) {
+        self.fit_size(avail_width, avail_height);
+        let (lines, _) = self.break_lines(avail_width);
+
+        let total = lines.len();
         self.lines.clear();
-        let mut rem_line = self.text.clone();
         let mut width: u32 = 0;
         let mut height: u32 = 0;
-        while rem_line.len() > 0 && height < avail_height {
-            let mut pos = rem_line.len();
-            let mut line_width;
-            loop {
-                line_width = self.font.get_width(&rem_line[0..pos]);
-                if line_width > avail_width as usize {
-                    pos = rem_line[0..pos].rfind(" ").unwrap();
-                } else {
-                    break;
-                }
-            };
-            let line = rem_line[0..pos].trim().to_string();
+        for line in lines {
+            let first = self.lines.is_empty();
+            if (!first && height + line.height > avail_height) || self.max_lines.is_some_and(|max_lines| self.lines.len() >= max_lines) {
+                break;
+            }
+            height += line.height;
             self.lines.push(line);
-            rem_line = rem_line[pos..].to_string();
-            width = max(width, line_width as u32);
-            height += self.line_height;
+        }
+        if self.lines.len() < total {
+            log::debug!("Showing {} of {} lines", self.lines.len(), total);
+            if let Some(last) = self.lines.pop() {
+                let last = match self.overflow {
+                    Overflow::Clip => last,
+                    Overflow::Ellipsis => Line { ends_paragraph: true, ..self.ellipsize(last, avail_width) },
+                    Overflow::Fade => Line { fade: true, ..last }
+                };
+                self.lines.push(last);
+            }
+        }
+        for line in self.lines.iter() {
+            width = max(width, line.width);
+        }
+        if self.align != TextAlign::Left {
+            width = max(width, avail_width);
         }
 
         self.layout.width = Some(width);
         self.layout.height = Some(height);
     }
-    
+
     fn run_layout_position(&mut self, offsetx: u32, offsety: u32) {
         self.layout.x = Some(offsetx);
         self.layout.y = Some(offsety);
@@ -75,12 +486,179 @@ impl LayoutItem for TextBox {
         let x = self.layout.x.unwrap_or(0);
         let mut y = self.layout.y.unwrap_or(0);
         let mut ret: Vec<Box<dyn PaintAction>> = vec![];
+        let box_width = self.layout.width.unwrap_or(0);
         for line in self.lines.iter() {
-            ret.push(Box::new(WriteText::new(line, &self.font, &self.color, x, y)));
-            y += self.line_height;
+            let spare = box_width.saturating_sub(line.width);
+            let x = match self.align {
+                TextAlign::Left | TextAlign::Justify => x,
+                TextAlign::Center => x + spare / 2,
+                TextAlign::Right => x + spare
+            };
+            let justify = self.align == TextAlign::Justify && !line.ends_paragraph && spare > 0;
+            let pieces = if justify { self.justify(line, spare) } else { None };
+            for (span, text, piece_x) in pieces.unwrap_or_else(|| line.fragments.iter().map(|f| (f.span, f.text.clone(), f.x)).collect()) {
+                let span = &self.spans[span];
+                let top = y + line.ascent - span.font.get_ascent() as u32;
+                let mut text = WriteText::new(&text, &span.font, &span.color, x + piece_x, top);
+                if line.fade {
+                    text = text.with_fade(x + line.width * 2 / 3, x + line.width);
+                }
+                ret.push(Box::new(text));
+            }
+            y += line.height;
         }
         ret
     }
 
 
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{color::Color, fonts::{Font, FontFactory}, layout::LayoutItem};
+
+    use super::{Fit, Overflow, TextBox};
+
+    fn font(size: f32) -> Font {
+        FontFactory::new(&[PathBuf::from("/usr/share/fonts")]).find_font("DejaVu Sans", 400, false, size).unwrap()
+    }
+
+    fn text_box(text: &str) -> TextBox {
+        sized_box(text, 20.0)
+    }
+
+    fn sized_box(text: &str, size: f32) -> TextBox {
+        TextBox::new(text, &font(size), &Color::new(255, 255, 255))
+    }
+
+    /// Fits the text into the box and returns the size it got.
+    fn fitted(mut text_box: TextBox, width: u32, height: u32) -> f32 {
+        text_box.fit_size(width, height);
+        text_box.spans[0].font.size()
+    }
+
+    /// Whether the text fits the box at `size`.
+    fn fits_at(text_box: &mut TextBox, size: f32, width: u32, height: u32) -> bool {
+        text_box.set_size(size);
+        text_box.fits(width, height)
+    }
+
+    fn shown(text_box: &TextBox) -> Vec<String> {
+        text_box.lines.iter().map(|line| line.fragments.iter().map(|f| f.text.as_str()).collect()).collect()
+    }
+
+    #[test]
+    fn breaks_words_wider_than_the_box() {
+        let mut text_box = text_box("Supercalifragilistic");
+        let (_, broken) = text_box.break_lines(40);
+        assert!(broken);
+        text_box.run_layout_top_down(40, 1000);
+        assert!(text_box.lines.len() > 1);
+        assert!(text_box.lines.iter().all(|line| line.width <= 40));
+        assert_eq!(shown(&text_box).concat(), "Supercalifragilistic");
+
+        // A single character wider than the box still gets a line of its own
+        text_box.run_layout_top_down(1, 1000);
+        assert_eq!(shown(&text_box).concat(), "Supercalifragilistic");
+    }
+
+    #[test]
+    fn breaks_multi_byte_text() {
+        let mut text_box = text_box("日本語のテキストを折り返します。Ünïcödé façade");
+        text_box.run_layout_top_down(80, 1000);
+        assert!(text_box.lines.len() > 2);
+        assert_eq!(shown(&text_box).concat().replace(' ', ""), "日本語のテキストを折り返します。Ünïcödéfaçade");
+    }
+
+    #[test]
+    fn keeps_newlines_and_blank_lines() {
+        let mut text_box = text_box("one\n\ntwo\n");
+        text_box.run_layout_top_down(1000, 1000);
+        assert_eq!(shown(&text_box), vec!["one", "", "two"]);
+        assert!(text_box.lines.iter().all(|line| line.ends_paragraph && line.height > 0));
+    }
+
+    #[test]
+    fn ellipsizes_the_last_of_max_lines() {
+        let mut text_box = text_box("The quick brown fox jumps over the lazy dog again and again").with_overflow(Some(2), Overflow::Ellipsis);
+        text_box.run_layout_top_down(150, 1000);
+        let lines = shown(&text_box);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].ends_with('\u{2026}'));
+        assert!(text_box.lines.iter().all(|line| line.width <= 150));
+    }
+
+    #[test]
+    fn cuts_lines_that_would_pass_the_bottom() {
+        let mut text_box = text_box("one\ntwo\nthree\nfour").with_overflow(None, Overflow::Ellipsis);
+        text_box.run_layout_top_down(1000, 1000);
+        let line_height = text_box.lines[0].height;
+        text_box.run_layout_top_down(1000, line_height * 2 + line_height / 2);
+        assert_eq!(shown(&text_box), vec!["one", "two\u{2026}"]);
+        assert_eq!(text_box.get_layout().height, Some(line_height * 2));
+
+        // A box shorter than a line still shows the first one, ended as the overflow says
+        text_box.run_layout_top_down(1000, line_height / 2);
+        assert_eq!(shown(&text_box), vec!["one\u{2026}"]);
+        assert_eq!(text_box.get_layout().height, Some(line_height));
+    }
+
+    #[test]
+    fn measures_lines_like_their_whole_text() {
+        let mut text_box = text_box("The quick brown fox jumps over the lazy dog");
+        text_box.run_layout_top_down(160, 1000);
+        let font = font(20.0);
+        for line in &text_box.lines {
+            let text: String = line.fragments.iter().map(|f| f.text.as_str()).collect();
+            assert_eq!(line.width, font.get_width(&text) as u32);
+            assert!(line.width <= 160);
+        }
+    }
+
+    #[test]
+    fn shrinks_to_the_largest_size_that_fits() {
+        let size = fitted(sized_box("Hello world", 100.0).with_fit(Fit::Shrink, None, None), 200, 1000);
+        assert!(size < 100.0 && size.fract() == 0.0);
+        let mut text_box = sized_box("Hello world", 100.0);
+        assert!(fits_at(&mut text_box, size, 200, 1000));
+        assert!(!fits_at(&mut text_box, size + 1.0, 200, 1000));
+
+        // Text that fits already keeps its size
+        assert_eq!(fitted(sized_box("Hi", 20.0).with_fit(Fit::Shrink, None, None), 200, 1000), 20.0);
+    }
+
+    #[test]
+    fn grows_and_fills_to_the_same_size() {
+        let grown = fitted(sized_box("Hello world", 10.0).with_fit(Fit::Grow, None, None).with_overflow(Some(1), Overflow::Clip), 300, 1000);
+        assert!(grown > 10.0);
+        let mut text_box = sized_box("Hello world", 10.0).with_overflow(Some(1), Overflow::Clip);
+        assert!(fits_at(&mut text_box, grown, 300, 1000));
+        assert!(!fits_at(&mut text_box, grown + 1.0, 300, 1000));
+        for start in [10.0, 200.0] {
+            let filled = fitted(sized_box("Hello world", start).with_fit(Fit::Fill, None, None).with_overflow(Some(1), Overflow::Clip), 300, 1000);
+            assert_eq!(filled, grown);
+        }
+
+        // Growing never shrinks text that is too big already
+        assert_eq!(fitted(sized_box("Hello world", 200.0).with_fit(Fit::Grow, None, None), 300, 1000), 200.0);
+    }
+
+    #[test]
+    fn keeps_within_min_and_max_size() {
+        assert_eq!(fitted(sized_box("Hello world", 100.0).with_fit(Fit::Shrink, Some(30.0), None), 20, 20), 30.0);
+        assert_eq!(fitted(sized_box("Hello world", 10.0).with_fit(Fit::Grow, None, Some(40.0)), 5000, 5000), 40.0);
+        assert_eq!(fitted(sized_box("Hello world", 10.0).with_fit(Fit::Fill, Some(12.0), Some(14.0)), 1, 1), 12.0);
+    }
+
+    #[test]
+    fn fits_with_fractional_bounds() {
+        let text_box = sized_box("Hello world headline", 11.3).with_fit(Fit::Shrink, Some(10.2), None).with_overflow(Some(1), Overflow::Clip);
+        assert!((10.2..=11.3).contains(&fitted(text_box, 95, 200)));
+        let text_box = sized_box("Hello world headline", 30.0).with_fit(Fit::Shrink, Some(10.2), Some(10.8)).with_overflow(Some(1), Overflow::Clip);
+        assert_eq!(fitted(text_box, 20, 200), 10.2);
+        let size = fitted(sized_box("Hello world headline", 30.5).with_fit(Fit::Shrink, Some(10.2), None).with_overflow(Some(1), Overflow::Clip), 200, 200);
+        assert!((11.0..30.5).contains(&size) && size.fract() == 0.0);
+    }
+}
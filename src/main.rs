@@ -1,44 +1,181 @@
 mod framebuffer;
+mod color;
+mod screeninfo;
+mod output;
 mod fonts;
 mod painter;
 mod layout;
 mod components;
 mod providers;
 mod runner;
+mod scheduler;
+mod transition;
 mod data;
 mod utils;
 mod models;
+mod error;
+mod watcher;
+mod cli;
+mod settings;
+mod theme;
 
-use std::env;
+use std::{fs, process, rc::Rc};
 
-use framebuffer::FrameBuffer;
+use clap::Parser;
+use cli::{Cli, Command};
+use fonts::{font_dirs, FontFactory};
+use output::{factory::{create_sink, spec_to_config}, DisplaySink};
 use runner::Runner;
+use serde_json::{json, Value};
+use settings::Settings;
+use utils::{load_config, set_cache_dir};
 
 pub fn main()  {
-    let args: Vec<String> = env::args().collect();
-    let mut fb: FrameBuffer = FrameBuffer::new();
-    let mut runner = Runner::new();
-    if args.len() == 1 {
-        runner.run(&mut fb);
-    } else if args.len() == 2 {
-        runner.save_page(&args[1], &mut fb);
-    }
-    
-    /*let mut painter = Painter::new();
-    let mut factory = ComponentFactory::new();
-    let top_components = factory.load_all("./pages");
-    let mut top_layout = top_components[0].produce();
-    top_layout.run_layout_1();
-    top_layout.run_layout_2(1920, 1080);
-    top_layout.run_layout_3(0, 0);
-
-    top_layout.log_layout(0);
-
-    let mut paint_actions = top_layout.get_paint_actions();
-    painter.add_actions(&mut paint_actions);
-
-    painter.paint_on(&mut fb);
-
-    //fb.send();
-    fb.save_png("out.png");*/
+    let cli = Cli::parse();
+    let mut logger = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+    if let Some(filters) = &cli.log_level {
+        logger.parse_filters(filters);
+    }
+    logger.init();
+    set_cache_dir(&cli.cache.clone().unwrap_or_else(|| format!("{}/cache", cli.root)));
+    let settings = match Settings::load(&cli.root) {
+        Ok(settings) => Rc::new(settings),
+        Err(error) => {
+            log::error!("Cannot load settings: {}", error);
+            process::exit(1);
+        }
+    };
+
+    match cli.command.as_ref().unwrap_or(&Command::Run { follow_edits: false }) {
+        Command::Run { follow_edits } => {
+            let mut sink = open_device(&cli, &settings);
+            let mut runner = Runner::new(&cli.root, settings.clone());
+            runner.set_follow_edits(*follow_edits);
+            runner.run(sink.as_mut());
+        },
+        Command::Render { page, output } => {
+            let path = output.clone().unwrap_or_else(|| format!("{}.png", page));
+            let mut sink = open_sink(&image_config(&cli, &settings, &path));
+            if let Err(error) = Runner::new(&cli.root, settings.clone()).save_page(page, sink.as_mut()) {
+                log::error!("{}", error);
+                process::exit(1);
+            }
+        },
+        Command::RenderAll { dir } => {
+            let runner = Runner::new(&cli.root, settings.clone());
+            let pages = runner.page_names().unwrap_or_else(|error| {
+                log::error!("{}", error);
+                process::exit(1);
+            });
+            let mut failed = false;
+            for page in pages {
+                let mut sink = open_sink(&image_config(&cli, &settings, &format!("{}/{}.png", dir, page)));
+                if let Err(error) = runner.save_page(&page, sink.as_mut()) {
+                    log::error!("{}", error);
+                    failed = true;
+                }
+            }
+            if failed {
+                process::exit(1);
+            }
+        },
+        Command::Fetch { provider } => {
+            match Runner::new(&cli.root, settings.clone()).fetch(provider) {
+                Ok(()) => log::info!("Stored data of {}", provider),
+                Err(error) => {
+                    log::error!("Provider {} failed: {}", provider, error);
+                    process::exit(1);
+                }
+            }
+        },
+        Command::Validate => {
+            if !Runner::new(&cli.root, settings.clone()).validate() {
+                process::exit(1);
+            }
+        },
+        Command::ListFonts => {
+            for name in FontFactory::new(&font_dirs(&cli.root, &settings.font_dirs)).font_names() {
+                println!("{}", name);
+            }
+        }
+    }
+}
+
+/// The output to run on: `--device`, else the one from the settings or `output.json`, else the framebuffer.
+fn device_config(cli: &Cli, settings: &Settings) -> Value {
+    let mut config = match &cli.device {
+        Some(spec) => spec_to_config(spec),
+        None => screen_config(cli, settings).unwrap_or_else(|| spec_to_config("fb:/dev/fb0"))
+    };
+    if let Some((width, height)) = cli.resolution {
+        config["width"] = json!(width);
+        config["height"] = json!(height);
+    }
+    config
+}
+
+/// An image file output, sized like the screen unless `--resolution` is given.
+fn image_config(cli: &Cli, settings: &Settings, path: &str) -> Value {
+    let file_type = if path.ends_with(".ppm") { "ppm" } else { "png" };
+    let mut config = spec_to_config(&format!("{}:{}", file_type, path));
+    let screen = screen_config(cli, settings).unwrap_or(Value::Null);
+    let (width, height) = cli.resolution.unwrap_or((
+        screen["width"].as_u64().unwrap_or(1920) as u32,
+        screen["height"].as_u64().unwrap_or(1080) as u32));
+    config["width"] = json!(width);
+    config["height"] = json!(height);
+    config
+}
+
+fn screen_config(cli: &Cli, settings: &Settings) -> Option<Value> {
+    if settings.output.is_some() {
+        return settings.output.clone();
+    }
+    if !fs::exists(format!("{}/output.json", cli.root)).unwrap_or(false) {
+        return None;
+    }
+    match load_config(&cli.root, "output") {
+        Ok(config) => Some(config),
+        Err(error) => {
+            log::error!("{}", error);
+            process::exit(1);
+        }
+    }
+}
+
+/// Opens the device output. Without `--device` or an output config, a missing or unreadable
+/// /dev/fb0 falls back to a 1920x1080 RGB565 canvas in memory so pages still run.
+fn open_device(cli: &Cli, settings: &Settings) -> Box<dyn DisplaySink> {
+    let config = device_config(cli, settings);
+    if cli.device.is_some() || screen_config(cli, settings).is_some() {
+        return open_sink(&config);
+    }
+    match create_sink(&config) {
+        Ok(sink) => {
+            log_sink(sink.as_ref());
+            sink
+        },
+        Err(error) => {
+            let (width, height) = cli.resolution.unwrap_or((1920, 1080));
+            log::warn!("Cannot open /dev/fb0 ({}), using a {}x{} RGB565 canvas in memory", error, width, height);
+            open_sink(&json!({"type": "memory", "width": width, "height": height, "format": "rgb565", "keep": 1}))
+        }
+    }
+}
+
+fn open_sink(config: &Value) -> Box<dyn DisplaySink> {
+    let sink = match create_sink(config) {
+        Ok(sink) => sink,
+        Err(error) => {
+            log::error!("Cannot open output: {}", error);
+            process::exit(1);
+        }
+    };
+    log_sink(sink.as_ref());
+    sink
+}
+
+fn log_sink(sink: &dyn DisplaySink) {
+    let info = sink.screen_info();
+    log::info!("Output {}x{} {:?}", info.width, info.height, info.format());
 }
@@ -0,0 +1,47 @@
+use crate::screeninfo::ScreenInfo;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32
+}
+
+fn changed_span(prev: &[u8], next: &[u8], bytes_per_pixel: usize) -> Option<(u32, u32)> {
+    let first = prev.iter().zip(next.iter()).position(|(a, b)| a != b)?;
+    let last = prev.iter().zip(next.iter()).rposition(|(a, b)| a != b)?;
+    Some(((first / bytes_per_pixel) as u32, (last / bytes_per_pixel) as u32 + 1))
+}
+
+/// Compares two encoded frames and returns the regions that differ. Consecutive changed
+/// rows are merged into one rectangle spanning the union of their changed columns.
+pub fn changed_rects(prev: &[u8], next: &[u8], info: &ScreenInfo) -> Vec<Rect> {
+    let mut rects: Vec<Rect> = vec![];
+    let mut current: Option<Rect> = None;
+    let row_bytes = info.width as usize * info.bytes_per_pixel();
+    for y in 0..info.height {
+        let start = info.pixel_index(0, y);
+        let span = changed_span(&prev[start..start + row_bytes], &next[start..start + row_bytes], info.bytes_per_pixel());
+        match (span, current.as_mut()) {
+            (Some((x1, x2)), Some(rect)) => {
+                let rx2 = rect.x + rect.width;
+                rect.x = rect.x.min(x1);
+                rect.width = rx2.max(x2) - rect.x;
+                rect.height += 1;
+            },
+            (Some((x1, x2)), None) => {
+                current = Some(Rect { x: x1, y, width: x2 - x1, height: 1 });
+            },
+            (None, _) => {
+                if let Some(rect) = current.take() {
+                    rects.push(rect);
+                }
+            }
+        }
+    }
+    if let Some(rect) = current.take() {
+        rects.push(rect);
+    }
+    rects
+}
@@ -0,0 +1,401 @@
+use std::{fs::{File, OpenOptions}, io::{self, Read}, mem::size_of, os::fd::AsRawFd, ptr, slice};
+
+use libc::{c_ulong, c_void};
+
+use crate::{framebuffer::FrameBuffer, screeninfo::{PixelFormat, ScreenInfo}};
+
+use super::{encode::{encode, Dither}, DisplaySink, SinkStats};
+
+const fn drm_iowr(nr: c_ulong, size: usize) -> c_ulong {
+    (3 << 30) | ((size as c_ulong) << 16) | (0x64 << 8) | nr
+}
+
+const DRM_IOCTL_MODE_GETRESOURCES: c_ulong = drm_iowr(0xA0, size_of::<DrmModeCardRes>());
+const DRM_IOCTL_MODE_GETCRTC: c_ulong = drm_iowr(0xA1, size_of::<DrmModeCrtc>());
+const DRM_IOCTL_MODE_SETCRTC: c_ulong = drm_iowr(0xA2, size_of::<DrmModeCrtc>());
+const DRM_IOCTL_MODE_GETENCODER: c_ulong = drm_iowr(0xA6, size_of::<DrmModeGetEncoder>());
+const DRM_IOCTL_MODE_GETCONNECTOR: c_ulong = drm_iowr(0xA7, size_of::<DrmModeGetConnector>());
+const DRM_IOCTL_MODE_ADDFB: c_ulong = drm_iowr(0xAE, size_of::<DrmModeFbCmd>());
+const DRM_IOCTL_MODE_RMFB: c_ulong = drm_iowr(0xAF, size_of::<u32>());
+const DRM_IOCTL_MODE_PAGE_FLIP: c_ulong = drm_iowr(0xB0, size_of::<DrmModeCrtcPageFlip>());
+const DRM_IOCTL_MODE_CREATE_DUMB: c_ulong = drm_iowr(0xB2, size_of::<DrmModeCreateDumb>());
+const DRM_IOCTL_MODE_MAP_DUMB: c_ulong = drm_iowr(0xB3, size_of::<DrmModeMapDumb>());
+const DRM_IOCTL_MODE_DESTROY_DUMB: c_ulong = drm_iowr(0xB4, size_of::<DrmModeDestroyDumb>());
+
+const DRM_MODE_CONNECTED: u32 = 1;
+const DRM_MODE_TYPE_PREFERRED: u32 = 1 << 3;
+const DRM_MODE_PAGE_FLIP_EVENT: u32 = 1;
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct DrmModeCardRes {
+    fb_id_ptr: u64,
+    crtc_id_ptr: u64,
+    connector_id_ptr: u64,
+    encoder_id_ptr: u64,
+    count_fbs: u32,
+    count_crtcs: u32,
+    count_connectors: u32,
+    count_encoders: u32,
+    min_width: u32,
+    max_width: u32,
+    min_height: u32,
+    max_height: u32
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct DrmModeModeInfo {
+    clock: u32,
+    hdisplay: u16,
+    hsync_start: u16,
+    hsync_end: u16,
+    htotal: u16,
+    hskew: u16,
+    vdisplay: u16,
+    vsync_start: u16,
+    vsync_end: u16,
+    vtotal: u16,
+    vscan: u16,
+    vrefresh: u32,
+    flags: u32,
+    mode_type: u32,
+    name: [u8; 32]
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct DrmModeGetConnector {
+    encoders_ptr: u64,
+    modes_ptr: u64,
+    props_ptr: u64,
+    prop_values_ptr: u64,
+    count_modes: u32,
+    count_props: u32,
+    count_encoders: u32,
+    encoder_id: u32,
+    connector_id: u32,
+    connector_type: u32,
+    connector_type_id: u32,
+    connection: u32,
+    mm_width: u32,
+    mm_height: u32,
+    subpixel: u32,
+    pad: u32
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct DrmModeGetEncoder {
+    encoder_id: u32,
+    encoder_type: u32,
+    crtc_id: u32,
+    possible_crtcs: u32,
+    possible_clones: u32
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct DrmModeCrtc {
+    set_connectors_ptr: u64,
+    count_connectors: u32,
+    crtc_id: u32,
+    fb_id: u32,
+    x: u32,
+    y: u32,
+    gamma_size: u32,
+    mode_valid: u32,
+    mode: DrmModeModeInfo
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct DrmModeCreateDumb {
+    height: u32,
+    width: u32,
+    bpp: u32,
+    flags: u32,
+    handle: u32,
+    pitch: u32,
+    size: u64
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct DrmModeMapDumb {
+    handle: u32,
+    pad: u32,
+    offset: u64
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct DrmModeDestroyDumb {
+    handle: u32
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct DrmModeFbCmd {
+    fb_id: u32,
+    width: u32,
+    height: u32,
+    pitch: u32,
+    bpp: u32,
+    depth: u32,
+    handle: u32
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct DrmModeCrtcPageFlip {
+    crtc_id: u32,
+    fb_id: u32,
+    flags: u32,
+    reserved: u32,
+    user_data: u64
+}
+
+fn drm_ioctl<T>(file: &File, request: c_ulong, arg: &mut T) -> io::Result<()> {
+    loop {
+        if unsafe { libc::ioctl(file.as_raw_fd(), request as _, arg as *mut T) } >= 0 {
+            return Ok(());
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+/// A dumb buffer registered as a framebuffer and mapped into memory. Whatever part of that
+/// was set up is torn down again on drop, so a failure halfway through doesn't leak it.
+struct DumbBuffer {
+    file: File,
+    handle: u32,
+    fb_id: u32,
+    map: *mut u8,
+    size: usize
+}
+
+impl DumbBuffer {
+    fn create(file: &File, width: u32, height: u32) -> io::Result<(Self, u32)> {
+        let mut create = DrmModeCreateDumb { width, height, bpp: 32, ..Default::default() };
+        drm_ioctl(file, DRM_IOCTL_MODE_CREATE_DUMB, &mut create)?;
+        let mut buffer = DumbBuffer { file: file.try_clone()?, handle: create.handle, fb_id: 0, map: ptr::null_mut(), size: 0 };
+        let mut fb_cmd = DrmModeFbCmd { width, height, pitch: create.pitch, bpp: 32, depth: 24, handle: create.handle, ..Default::default() };
+        drm_ioctl(file, DRM_IOCTL_MODE_ADDFB, &mut fb_cmd)?;
+        buffer.fb_id = fb_cmd.fb_id;
+        let mut map_dumb = DrmModeMapDumb { handle: create.handle, ..Default::default() };
+        drm_ioctl(file, DRM_IOCTL_MODE_MAP_DUMB, &mut map_dumb)?;
+        let size = create.size as usize;
+        let map = unsafe {
+            libc::mmap(ptr::null_mut(), size, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED, file.as_raw_fd(), map_dumb.offset as libc::off_t)
+        };
+        if map == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        buffer.map = map as *mut u8;
+        buffer.size = size;
+        Ok((buffer, create.pitch))
+    }
+
+    fn data(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.map, self.size) }
+    }
+}
+
+impl Drop for DumbBuffer {
+    fn drop(&mut self) {
+        if !self.map.is_null() {
+            unsafe {
+                libc::munmap(self.map as *mut c_void, self.size);
+            }
+        }
+        if self.fb_id != 0 {
+            let _ = drm_ioctl(&self.file, DRM_IOCTL_MODE_RMFB, &mut self.fb_id);
+        }
+        let _ = drm_ioctl(&self.file, DRM_IOCTL_MODE_DESTROY_DUMB, &mut DrmModeDestroyDumb { handle: self.handle });
+    }
+}
+
+/// Shows frames through DRM/KMS: picks a connected connector and mode, scans out of
+/// two dumb buffers and page-flips between them.
+pub struct DrmSink {
+    file: File,
+    info: ScreenInfo,
+    dither: Dither,
+    connector_id: u32,
+    crtc_id: u32,
+    mode: DrmModeModeInfo,
+    saved_crtc: DrmModeCrtc,
+    buffers: Vec<DumbBuffer>,
+    front: usize,
+    mode_set: bool,
+    stats: SinkStats
+}
+
+impl DrmSink {
+    /// Opens `device`, optionally restricted to one connector id and a `WIDTHxHEIGHT` mode.
+    pub fn open(device: &str, connector: Option<u32>, mode_name: Option<&str>, dither: Dither) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(device)?;
+
+        let mut res = DrmModeCardRes::default();
+        drm_ioctl(&file, DRM_IOCTL_MODE_GETRESOURCES, &mut res)?;
+        let mut crtc_ids: Vec<u32> = vec![0; res.count_crtcs as usize];
+        let mut connector_ids: Vec<u32> = vec![0; res.count_connectors as usize];
+        let mut encoder_ids: Vec<u32> = vec![0; res.count_encoders as usize];
+        res = DrmModeCardRes {
+            crtc_id_ptr: crtc_ids.as_mut_ptr() as u64,
+            connector_id_ptr: connector_ids.as_mut_ptr() as u64,
+            encoder_id_ptr: encoder_ids.as_mut_ptr() as u64,
+            count_crtcs: res.count_crtcs,
+            count_connectors: res.count_connectors,
+            count_encoders: res.count_encoders,
+            ..Default::default()
+        };
+        drm_ioctl(&file, DRM_IOCTL_MODE_GETRESOURCES, &mut res)?;
+
+        for connector_id in connector_ids.iter().filter(|id| connector.is_none() || connector == Some(**id)) {
+            let mut conn = DrmModeGetConnector { connector_id: *connector_id, ..Default::default() };
+            drm_ioctl(&file, DRM_IOCTL_MODE_GETCONNECTOR, &mut conn)?;
+            if conn.connection != DRM_MODE_CONNECTED || conn.count_modes == 0 {
+                continue;
+            }
+            let mut modes: Vec<DrmModeModeInfo> = vec![DrmModeModeInfo::default(); conn.count_modes as usize];
+            let mut conn_encoders: Vec<u32> = vec![0; conn.count_encoders as usize];
+            conn = DrmModeGetConnector {
+                connector_id: *connector_id,
+                modes_ptr: modes.as_mut_ptr() as u64,
+                encoders_ptr: conn_encoders.as_mut_ptr() as u64,
+                count_modes: conn.count_modes,
+                count_encoders: conn.count_encoders,
+                ..Default::default()
+            };
+            drm_ioctl(&file, DRM_IOCTL_MODE_GETCONNECTOR, &mut conn)?;
+            modes.truncate(conn.count_modes as usize);
+            conn_encoders.truncate(conn.count_encoders as usize);
+
+            let mode = match mode_name {
+                Some(name) => modes.iter().find(|m| format!("{}x{}", m.hdisplay, m.vdisplay) == name),
+                None => modes.iter().find(|m| m.mode_type & DRM_MODE_TYPE_PREFERRED != 0).or(modes.first())
+            };
+            let Some(mode) = mode.copied() else {
+                continue;
+            };
+            let Some(crtc_id) = find_crtc(&file, &conn, &conn_encoders, &crtc_ids) else {
+                continue;
+            };
+
+            let mut saved_crtc = DrmModeCrtc { crtc_id, ..Default::default() };
+            drm_ioctl(&file, DRM_IOCTL_MODE_GETCRTC, &mut saved_crtc)?;
+
+            let width = mode.hdisplay as u32;
+            let height = mode.vdisplay as u32;
+            let mut buffers = vec![];
+            let mut pitch = width * 4;
+            for _ in 0..2 {
+                let (buffer, buffer_pitch) = DumbBuffer::create(&file, width, height)?;
+                buffers.push(buffer);
+                pitch = buffer_pitch;
+            }
+            let mut info = ScreenInfo::from_format(width, height, PixelFormat::Xrgb8888);
+            info.line_length = pitch;
+            return Ok(DrmSink {
+                file,
+                info,
+                dither,
+                connector_id: *connector_id,
+                crtc_id,
+                mode,
+                saved_crtc,
+                buffers,
+                front: 0,
+                mode_set: false,
+                stats: SinkStats::default()
+            });
+        }
+        Err(io::Error::new(io::ErrorKind::NotFound, format!("No connected display with a usable mode on {}", device)))
+    }
+
+    fn set_crtc(&self, fb_id: u32, mode: DrmModeModeInfo) -> io::Result<()> {
+        let mut connector_id = self.connector_id;
+        let mut crtc = DrmModeCrtc {
+            set_connectors_ptr: &mut connector_id as *mut u32 as u64,
+            count_connectors: 1,
+            crtc_id: self.crtc_id,
+            fb_id,
+            mode_valid: 1,
+            mode,
+            ..Default::default()
+        };
+        drm_ioctl(&self.file, DRM_IOCTL_MODE_SETCRTC, &mut crtc)
+    }
+
+    fn page_flip(&mut self, buffer: usize) -> io::Result<()> {
+        let mut flip = DrmModeCrtcPageFlip { crtc_id: self.crtc_id, fb_id: self.buffers[buffer].fb_id, flags: DRM_MODE_PAGE_FLIP_EVENT, ..Default::default() };
+        drm_ioctl(&self.file, DRM_IOCTL_MODE_PAGE_FLIP, &mut flip)?;
+        // Block until the flip-complete event arrives so the old buffer is free to draw into
+        let mut event = [0u8; 1024];
+        let _ = self.file.read(&mut event)?;
+        Ok(())
+    }
+}
+
+fn find_crtc(file: &File, conn: &DrmModeGetConnector, encoder_ids: &[u32], crtc_ids: &[u32]) -> Option<u32> {
+    if conn.encoder_id != 0 {
+        let mut enc = DrmModeGetEncoder { encoder_id: conn.encoder_id, ..Default::default() };
+        if drm_ioctl(file, DRM_IOCTL_MODE_GETENCODER, &mut enc).is_ok() && enc.crtc_id != 0 {
+            return Some(enc.crtc_id);
+        }
+    }
+    for encoder_id in encoder_ids {
+        let mut enc = DrmModeGetEncoder { encoder_id: *encoder_id, ..Default::default() };
+        if drm_ioctl(file, DRM_IOCTL_MODE_GETENCODER, &mut enc).is_err() {
+            continue;
+        }
+        if let Some((_, crtc_id)) = crtc_ids.iter().enumerate().find(|(i, _)| enc.possible_crtcs & (1 << i) != 0) {
+            return Some(*crtc_id);
+        }
+    }
+    None
+}
+
+impl DisplaySink for DrmSink {
+    fn screen_info(&self) -> ScreenInfo {
+        self.info.clone()
+    }
+
+    fn show(&mut self, frame_buffer: &FrameBuffer) {
+        let data = encode(frame_buffer, &self.info, &self.dither);
+        let target = if self.mode_set { (self.front + 1) % self.buffers.len() } else { self.front };
+        let buffer = self.buffers[target].data();
+        let len = data.len().min(buffer.len());
+        buffer[..len].copy_from_slice(&data[..len]);
+        let res = if self.mode_set {
+            self.page_flip(target)
+        } else {
+            self.set_crtc(self.buffers[target].fb_id, self.mode).map(|_| self.mode_set = true)
+        };
+        match res {
+            Ok(_) => {
+                self.front = target;
+                self.stats.record(len);
+            },
+            Err(error) => log::error!("{}", error)
+        }
+    }
+
+    fn stats(&self) -> SinkStats {
+        self.stats.clone()
+    }
+}
+
+impl Drop for DrmSink {
+    fn drop(&mut self) {
+        if self.saved_crtc.mode_valid != 0 {
+            let _ = self.set_crtc(self.saved_crtc.fb_id, self.saved_crtc.mode);
+        }
+        // The buffers are destroyed when dropped, after the old framebuffer is back on screen
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{framebuffer::FrameBuffer, screeninfo::ScreenInfo};
+
+const BAYER_4X4: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5]
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Dither {
+    None,
+    Ordered,
+    FloydSteinberg
+}
+
+impl Dither {
+    pub fn from_string(str: &str) -> Self {
+        match str {
+            "ordered" | "bayer" => Dither::Ordered,
+            "floyd-steinberg" | "floydsteinberg" | "fs" => Dither::FloydSteinberg,
+            _ => Dither::None
+        }
+    }
+}
+
+/// Converts the RGBA working canvas into the pixel layout described by `info`.
+pub fn encode(fb: &FrameBuffer, info: &ScreenInfo, dither: &Dither) -> Vec<u8> {
+    let mut out: Vec<u8> = vec![0; info.size()];
+    let width = info.width.min(fb.width());
+    let height = info.height.min(fb.height());
+    let lossy = [info.red, info.green, info.blue].iter().any(|c| c.length < 8);
+    match dither {
+        Dither::Ordered if lossy => {
+            let steps = [info.red, info.green, info.blue].map(|c| 255.0 / ((1u32 << c.length.min(8)) - 1) as f32);
+            for y in 0..height {
+                for x in 0..width {
+                    let threshold = ((BAYER_4X4[(y % 4) as usize][(x % 4) as usize] as f32 + 0.5) / 16.0) - 0.5;
+                    let (r, g, b) = fb.get_rgb(x, y);
+                    let mut dithered = [0u8; 3];
+                    for (c, v) in [r, g, b].into_iter().enumerate() {
+                        dithered[c] = (v as f32 + (threshold * steps[c])).round().clamp(0.0, 255.0) as u8;
+                    }
+                    info.write_pixel(&mut out, info.pixel_index(x, y), info.pack(dithered[0], dithered[1], dithered[2]));
+                }
+            }
+        },
+        Dither::FloydSteinberg if lossy => {
+            let channels = [info.red, info.green, info.blue];
+            let row_len = width as usize + 2;
+            let mut errors = vec![[0.0f32; 3]; row_len];
+            let mut next_errors = vec![[0.0f32; 3]; row_len];
+            for y in 0..height {
+                for x in 0..width {
+                    let (r, g, b) = fb.get_rgb(x, y);
+                    let mut quantized = [0u8; 3];
+                    let ex = x as usize + 1;
+                    for (c, v) in [r, g, b].into_iter().enumerate() {
+                        let wanted = (v as f32 + errors[ex][c]).clamp(0.0, 255.0);
+                        let q = channels[c].quantize(wanted.round() as u8);
+                        let err = wanted - q as f32;
+                        errors[ex + 1][c] += err * 7.0 / 16.0;
+                        next_errors[ex - 1][c] += err * 3.0 / 16.0;
+                        next_errors[ex][c] += err * 5.0 / 16.0;
+                        next_errors[ex + 1][c] += err / 16.0;
+                        quantized[c] = q;
+                    }
+                    info.write_pixel(&mut out, info.pixel_index(x, y), info.pack(quantized[0], quantized[1], quantized[2]));
+                }
+                std::mem::swap(&mut errors, &mut next_errors);
+                next_errors.iter_mut().for_each(|e| *e = [0.0; 3]);
+            }
+        },
+        _ => {
+            for y in 0..height {
+                for x in 0..width {
+                    let (r, g, b) = fb.get_rgb(x, y);
+                    info.write_pixel(&mut out, info.pixel_index(x, y), info.pack(r, g, b));
+                }
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,64 @@
+use std::io;
+
+use serde_json::{json, Value};
+
+use crate::screeninfo::{PixelFormat, ScreenInfo};
+
+use super::{encode::Dither, orientation::{Orientation, OrientedSink}, drm::DrmSink, fbdev::FbDevSink, imagefile::{ImageFileSink, ImageFileType}, memory::MemorySink, stdout::StdoutSink, DisplaySink};
+
+/// Parses a command line output spec such as `fb:/dev/fb1`, `drm:/dev/dri/card0`, `png:frames/{n}.png`,
+/// `ppm:out.ppm`, `stdout` or `memory` into the same shape as an output config file.
+pub fn spec_to_config(spec: &str) -> Value {
+    let (sink_type, arg) = match spec.split_once(':') {
+        Some((t, a)) => (t, Some(a)),
+        None => (spec, None)
+    };
+    match sink_type {
+        "fb" | "fbdev" => json!({"type": "fbdev", "device": arg.unwrap_or("/dev/fb0")}),
+        "drm" => json!({"type": "drm", "device": arg.unwrap_or("/dev/dri/card0")}),
+        "png" => json!({"type": "png", "path": arg.unwrap_or("out.png")}),
+        "ppm" => json!({"type": "ppm", "path": arg.unwrap_or("out.ppm")}),
+        _ => json!({"type": sink_type})
+    }
+}
+
+pub fn create_sink(value: &Value) -> io::Result<Box<dyn DisplaySink>> {
+    let sink = create_device_sink(value)?;
+    let orientation = Orientation::new(value["rotate"].as_u64().unwrap_or(0) as u32, value["mirror"].as_str().unwrap_or("none"));
+    if orientation.is_identity() {
+        Ok(sink)
+    } else {
+        Ok(Box::new(OrientedSink::new(sink, orientation)))
+    }
+}
+
+fn create_device_sink(value: &Value) -> io::Result<Box<dyn DisplaySink>> {
+    let width = value["width"].as_u64().unwrap_or(1920) as u32;
+    let height = value["height"].as_u64().unwrap_or(1080) as u32;
+    let format = value["format"].as_str().and_then(PixelFormat::from_string).unwrap_or(PixelFormat::Xrgb8888);
+    let info = ScreenInfo::from_format(width, height, format);
+    let dither = Dither::from_string(value["dither"].as_str().unwrap_or("none"));
+    match value["type"].as_str() {
+        Some("fbdev") | None => {
+            let device = value["device"].as_str().unwrap_or("/dev/fb0");
+            let double_buffer = value["double_buffer"].as_bool().unwrap_or(true);
+            Ok(Box::new(FbDevSink::open(device, dither, double_buffer)?))
+        },
+        Some("drm") => {
+            let device = value["device"].as_str().unwrap_or("/dev/dri/card0");
+            let connector = value["connector"].as_u64().map(|id| id as u32);
+            Ok(Box::new(DrmSink::open(device, connector, value["mode"].as_str(), dither)?))
+        },
+        Some("png") => Ok(Box::new(ImageFileSink::new(value["path"].as_str().unwrap_or("out.png"), ImageFileType::Png, info))),
+        Some("ppm") => Ok(Box::new(ImageFileSink::new(value["path"].as_str().unwrap_or("out.ppm"), ImageFileType::Ppm, info))),
+        Some("stdout") => Ok(Box::new(StdoutSink::new(info, dither))),
+        Some("memory") => {
+            let sink = MemorySink::new(info, dither);
+            match value["keep"].as_u64() {
+                Some(count) => Ok(Box::new(sink.keeping(count as usize))),
+                None => Ok(Box::new(sink))
+            }
+        },
+        Some(other) => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown output type {}", other)))
+    }
+}
@@ -0,0 +1,117 @@
+use std::{fs::{File, OpenOptions}, io::{self, Seek, SeekFrom, Write}};
+
+use crate::{framebuffer::FrameBuffer, screeninfo::{pan_display, restore_mode, save_mode, setup_double_buffer, wait_for_vsync, SavedMode, ScreenInfo}};
+
+use super::{dirty::{changed_rects, Rect}, encode::{encode, Dither}, DisplaySink, SinkStats};
+
+/// Writes frames to an fbdev device. When the driver allows a double-height virtual
+/// framebuffer, frames are drawn into the hidden half and flipped in by panning.
+/// The original mode is restored when the sink is dropped.
+pub struct FbDevSink {
+    file: File,
+    mode: Option<SavedMode>,
+    info: ScreenInfo,
+    dither: Dither,
+    buffers: usize,
+    front: usize,
+    previous: Vec<Option<Vec<u8>>>,
+    stats: SinkStats
+}
+
+impl FbDevSink {
+    pub fn open(device: &str, dither: Dither, double_buffer: bool) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(device)?;
+        let mode = save_mode(&file).ok();
+        let buffers = if double_buffer && setup_double_buffer(&file).unwrap_or(false) { 2 } else { 1 };
+        let info = ScreenInfo::from_device(device)?;
+        if buffers == 2 {
+            let _ = pan_display(&file, 0);
+        }
+        Ok(FbDevSink { file, mode, info, dither, buffers, front: 0, previous: vec![None; buffers], stats: SinkStats::default() })
+    }
+
+    fn write_rects(&mut self, buffer: usize, data: &[u8], rects: &[Rect]) -> io::Result<usize> {
+        let bytes_per_pixel = self.info.bytes_per_pixel();
+        let buffer_offset = buffer * self.info.size();
+        let mut written = 0;
+        for rect in rects {
+            let row_bytes = rect.width as usize * bytes_per_pixel;
+            for y in rect.y..(rect.y + rect.height) {
+                let start = self.info.pixel_index(rect.x, y);
+                self.file.seek(SeekFrom::Start((buffer_offset + start) as u64))?;
+                self.file.write_all(&data[start..start + row_bytes])?;
+                written += row_bytes;
+            }
+        }
+        Ok(written)
+    }
+
+    fn flip(&mut self, buffer: usize) -> bool {
+        let _ = wait_for_vsync(&self.file);
+        match pan_display(&self.file, buffer as u32 * self.info.height) {
+            Ok(_) => {
+                self.front = buffer;
+                true
+            },
+            Err(error) => {
+                log::warn!("Page flip failed, falling back to single buffering: {}", error);
+                let _ = pan_display(&self.file, 0);
+                self.buffers = 1;
+                self.front = 0;
+                self.previous = vec![None];
+                false
+            }
+        }
+    }
+}
+
+impl DisplaySink for FbDevSink {
+    fn screen_info(&self) -> ScreenInfo {
+        self.info.clone()
+    }
+
+    fn show(&mut self, frame_buffer: &FrameBuffer) {
+        let data = encode(frame_buffer, &self.info, &self.dither);
+        let target = (self.front + 1) % self.buffers;
+        let rects = match &self.previous[target] {
+            Some(previous) => changed_rects(previous, &data, &self.info),
+            None => vec![Rect { x: 0, y: 0, width: self.info.width, height: self.info.height }]
+        };
+        let written = match self.write_rects(target, &data, &rects) {
+            Ok(written) => written,
+            Err(error) => {
+                log::error!("{}", error);
+                self.previous[target] = None;
+                return;
+            }
+        };
+        if self.buffers > 1 && !self.flip(target) {
+            // The frame went to the hidden half, write all of it again to the shown one
+            let full = [Rect { x: 0, y: 0, width: self.info.width, height: self.info.height }];
+            match self.write_rects(0, &data, &full) {
+                Ok(written) => {
+                    self.previous[0] = Some(data);
+                    self.stats.record(written);
+                },
+                Err(error) => log::error!("{}", error)
+            }
+            return;
+        }
+        self.previous[target] = Some(data);
+        self.stats.record(written);
+    }
+
+    fn stats(&self) -> SinkStats {
+        self.stats.clone()
+    }
+}
+
+impl Drop for FbDevSink {
+    fn drop(&mut self) {
+        if let Some(mode) = &self.mode {
+            if let Err(error) = restore_mode(&self.file, mode) {
+                log::warn!("Cannot restore the framebuffer mode: {}", error);
+            }
+        }
+    }
+}
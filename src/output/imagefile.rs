@@ -0,0 +1,88 @@
+use std::{fs::{self, File}, io::{self, BufWriter, Write}};
+
+use png_encode_mini::write_rgba_from_u32;
+
+use crate::{framebuffer::FrameBuffer, screeninfo::ScreenInfo};
+
+use super::{DisplaySink, SinkStats};
+
+#[derive(Debug, Clone)]
+pub enum ImageFileType {
+    Png,
+    Ppm
+}
+
+/// Saves frames as image files. A `{n}` in the path is replaced by the frame number
+/// to produce a sequence, otherwise the same file is overwritten.
+pub struct ImageFileSink {
+    path: String,
+    file_type: ImageFileType,
+    info: ScreenInfo,
+    frame: u32,
+    stats: SinkStats
+}
+
+impl ImageFileSink {
+    pub fn new(path: &str, file_type: ImageFileType, info: ScreenInfo) -> Self {
+        ImageFileSink { path: path.to_string(), file_type, info, frame: 0, stats: SinkStats::default() }
+    }
+
+    fn next_path(&mut self) -> String {
+        let path = self.path.replace("{n}", &format!("{:05}", self.frame));
+        self.frame += 1;
+        path
+    }
+}
+
+impl DisplaySink for ImageFileSink {
+    fn screen_info(&self) -> ScreenInfo {
+        self.info.clone()
+    }
+
+    fn show(&mut self, frame_buffer: &FrameBuffer) {
+        let path = self.next_path();
+        let res = match self.file_type {
+            ImageFileType::Png => write_png(&path, frame_buffer),
+            ImageFileType::Ppm => write_ppm(&path, frame_buffer),
+        };
+        match res.and_then(|_| fs::metadata(&path)) {
+            Ok(metadata) => self.stats.record(metadata.len() as usize),
+            Err(error) => log::error!("Cannot write {}: {}", path, error)
+        }
+    }
+
+    fn stats(&self) -> SinkStats {
+        self.stats.clone()
+    }
+}
+
+fn write_png(path: &str, frame_buffer: &FrameBuffer) -> io::Result<()> {
+    let width = frame_buffer.width();
+    let height = frame_buffer.height();
+    let mut buf32:Vec<u32> = vec![0; (width * height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            // png_encode_mini writes rows bottom-up, so feed it a vertically flipped image
+            let out_ptr = x as usize + ((height - 1 - y) * width) as usize;
+            let (r, g, b) = frame_buffer.get_rgb(x, y);
+            let a: u8 = 255;
+            buf32[out_ptr] = (r as u32) | ((g as u32) << 8) | ((b as u32) << 16) | ((a as u32) << 24);
+        }
+    }
+    let mut file = File::create(path)?;
+    write_rgba_from_u32(&mut file, &buf32, width, height)
+}
+
+fn write_ppm(path: &str, frame_buffer: &FrameBuffer) -> io::Result<()> {
+    let width = frame_buffer.width();
+    let height = frame_buffer.height();
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(writer, "P6\n{} {}\n255\n", width, height)?;
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = frame_buffer.get_rgb(x, y);
+            writer.write_all(&[r, g, b])?;
+        }
+    }
+    writer.flush()
+}
@@ -0,0 +1,49 @@
+use crate::{framebuffer::FrameBuffer, screeninfo::ScreenInfo};
+
+use super::{encode::{encode, Dither}, DisplaySink, SinkStats};
+
+/// Keeps the shown frames in memory, for headless rendering and tests.
+pub struct MemorySink {
+    info: ScreenInfo,
+    dither: Dither,
+    keep: Option<usize>,
+    frames: Vec<Vec<u8>>,
+    stats: SinkStats
+}
+
+impl MemorySink {
+    pub fn new(info: ScreenInfo, dither: Dither) -> Self {
+        MemorySink { info, dither, keep: None, frames: vec![], stats: SinkStats::default() }
+    }
+
+    /// Only keeps the last `count` frames, so a long run doesn't grow without bound.
+    pub fn keeping(mut self, count: usize) -> Self {
+        self.keep = Some(count);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn frames(&self) -> &Vec<Vec<u8>> {
+        &self.frames
+    }
+}
+
+impl DisplaySink for MemorySink {
+    fn screen_info(&self) -> ScreenInfo {
+        self.info.clone()
+    }
+
+    fn show(&mut self, frame_buffer: &FrameBuffer) {
+        let data = encode(frame_buffer, &self.info, &self.dither);
+        self.stats.record(data.len());
+        self.frames.push(data);
+        if let Some(keep) = self.keep {
+            let excess = self.frames.len().saturating_sub(keep);
+            self.frames.drain(..excess);
+        }
+    }
+
+    fn stats(&self) -> SinkStats {
+        self.stats.clone()
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{framebuffer::FrameBuffer, screeninfo::ScreenInfo};
+
+pub mod factory;
+pub mod encode;
+pub mod dirty;
+pub mod orientation;
+pub mod fbdev;
+pub mod drm;
+pub mod imagefile;
+pub mod stdout;
+pub mod memory;
+
+#[derive(Debug, Clone, Default)]
+pub struct SinkStats {
+    pub frames: u64,
+    pub total_bytes: u64,
+    pub last_frame_bytes: usize
+}
+
+impl SinkStats {
+    pub fn record(&mut self, bytes: usize) {
+        self.frames += 1;
+        self.total_bytes += bytes as u64;
+        self.last_frame_bytes = bytes;
+    }
+}
+
+pub trait DisplaySink {
+    fn screen_info(&self) -> ScreenInfo;
+
+    /// Size of the canvas pages are laid out and painted on.
+    fn canvas_size(&self) -> (u32, u32) {
+        let info = self.screen_info();
+        (info.width, info.height)
+    }
+
+    fn show(&mut self, frame_buffer: &FrameBuffer);
+    fn stats(&self) -> SinkStats;
+}
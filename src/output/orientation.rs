@@ -0,0 +1,96 @@
+use crate::{framebuffer::FrameBuffer, screeninfo::ScreenInfo};
+
+use super::{DisplaySink, SinkStats};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Orientation {
+    rotate: u32,
+    mirror_h: bool,
+    mirror_v: bool
+}
+
+impl Orientation {
+    pub fn new(rotate: u32, mirror: &str) -> Self {
+        let rotate = match rotate % 360 {
+            90 => 90,
+            180 => 180,
+            270 => 270,
+            _ => 0
+        };
+        let mirror_h = mirror == "horizontal" || mirror == "both";
+        let mirror_v = mirror == "vertical" || mirror == "both";
+        Orientation { rotate, mirror_h, mirror_v }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.rotate == 0 && !self.mirror_h && !self.mirror_v
+    }
+
+    /// Size of the layout space for a device of the given size.
+    pub fn logical_size(&self, device_width: u32, device_height: u32) -> (u32, u32) {
+        if self.rotate == 90 || self.rotate == 270 {
+            (device_height, device_width)
+        } else {
+            (device_width, device_height)
+        }
+    }
+
+    /// Maps a device pixel back to the layout pixel that should be shown there. The layout
+    /// is mirrored first and then rotated clockwise by `rotate` degrees onto the device.
+    pub fn to_logical(&self, dx: u32, dy: u32, logical_width: u32, logical_height: u32) -> (u32, u32) {
+        let (lx, ly) = match self.rotate {
+            90 => (dy, logical_height - 1 - dx),
+            180 => (logical_width - 1 - dx, logical_height - 1 - dy),
+            270 => (logical_width - 1 - dy, dx),
+            _ => (dx, dy)
+        };
+        let lx = if self.mirror_h { logical_width - 1 - lx } else { lx };
+        let ly = if self.mirror_v { logical_height - 1 - ly } else { ly };
+        (lx, ly)
+    }
+}
+
+/// Wraps another sink, presenting a rotated and/or mirrored canvas to the layout.
+pub struct OrientedSink {
+    inner: Box<dyn DisplaySink>,
+    orientation: Orientation,
+    device_buffer: FrameBuffer
+}
+
+impl OrientedSink {
+    pub fn new(inner: Box<dyn DisplaySink>, orientation: Orientation) -> Self {
+        let info = inner.screen_info();
+        let device_buffer = FrameBuffer::new(info.width, info.height);
+        OrientedSink { inner, orientation, device_buffer }
+    }
+}
+
+impl DisplaySink for OrientedSink {
+    fn screen_info(&self) -> ScreenInfo {
+        self.inner.screen_info()
+    }
+
+    fn canvas_size(&self) -> (u32, u32) {
+        let (width, height) = self.inner.canvas_size();
+        self.orientation.logical_size(width, height)
+    }
+
+    fn show(&mut self, frame_buffer: &FrameBuffer) {
+        let logical_width = frame_buffer.width();
+        let logical_height = frame_buffer.height();
+        for dy in 0..self.device_buffer.height() {
+            for dx in 0..self.device_buffer.width() {
+                let (lx, ly) = self.orientation.to_logical(dx, dy, logical_width, logical_height);
+                if lx < logical_width && ly < logical_height {
+                    let (r, g, b) = frame_buffer.get_rgb(lx, ly);
+                    self.device_buffer.set_rgb(dx, dy, r, g, b);
+                }
+            }
+        }
+        self.inner.show(&self.device_buffer);
+    }
+
+    fn stats(&self) -> SinkStats {
+        self.inner.stats()
+    }
+}
@@ -0,0 +1,37 @@
+use std::io::{self, Write};
+
+use crate::{framebuffer::FrameBuffer, screeninfo::ScreenInfo};
+
+use super::{encode::{encode, Dither}, DisplaySink, SinkStats};
+
+/// Writes every frame as raw pixels in the configured format, e.g. for piping into ffmpeg.
+pub struct StdoutSink {
+    info: ScreenInfo,
+    dither: Dither,
+    stats: SinkStats
+}
+
+impl StdoutSink {
+    pub fn new(info: ScreenInfo, dither: Dither) -> Self {
+        StdoutSink { info, dither, stats: SinkStats::default() }
+    }
+}
+
+impl DisplaySink for StdoutSink {
+    fn screen_info(&self) -> ScreenInfo {
+        self.info.clone()
+    }
+
+    fn show(&mut self, frame_buffer: &FrameBuffer) {
+        let data = encode(frame_buffer, &self.info, &self.dither);
+        let mut out = io::stdout().lock();
+        match out.write_all(&data).and_then(|_| out.flush()) {
+            Ok(_) => self.stats.record(data.len()),
+            Err(error) => log::error!("{}", error)
+        }
+    }
+
+    fn stats(&self) -> SinkStats {
+        self.stats.clone()
+    }
+}
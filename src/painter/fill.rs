@@ -1,6 +1,6 @@
 use std::cmp::{max, min};
 
-use crate::framebuffer::{Color, FrameBuffer};
+use crate::{color::Color, framebuffer::FrameBuffer};
 
 use super::PaintAction;
 
@@ -28,7 +28,7 @@ impl PaintAction for Fill {
         let ey = max(self.y1, self.y2) as i32;
         for y in sy..ey {
             for x in sx..ex {
-                fb.poke(x, y, self.color.red, self.color.green, self.color.blue, 1.0);
+                fb.poke(x, y, self.color.red, self.color.green, self.color.blue, self.color.opacity());
             }
         }
     }
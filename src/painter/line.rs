@@ -1,4 +1,4 @@
-use crate::framebuffer::{Color, FrameBuffer};
+use crate::{color::Color, framebuffer::FrameBuffer};
 
 use super::PaintAction;
 
@@ -38,7 +38,7 @@ impl PaintAction for PaintLine {
             }
             for x in sx..ex {
                 let y = sy + ((x - sx) * (ey - sy) / (ex - sx));
-                fb.poke(x, y, self.color.red, self.color.green, self.color.blue, 1.0);
+                fb.poke(x, y, self.color.red, self.color.green, self.color.blue, self.color.opacity());
             }
         } else {
             if self.y1 < self.y2 {
@@ -54,7 +54,7 @@ impl PaintAction for PaintLine {
             }
             for y in sy..ey {
                 let x = sx + ((y - sy) * (ex - sx) / (ey - sy));
-                fb.poke(x, y, self.color.red, self.color.green, self.color.blue, 1.0);
+                fb.poke(x, y, self.color.red, self.color.green, self.color.blue, self.color.opacity());
             }
         }  
     }
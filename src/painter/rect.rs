@@ -1,6 +1,6 @@
 use std::cmp::{max, min};
 
-use crate::framebuffer::{Color, FrameBuffer};
+use crate::{color::Color, framebuffer::FrameBuffer};
 
 use super::PaintAction;
 
@@ -27,12 +27,12 @@ impl PaintAction for PaintRect {
         let sy = min(self.y1, self.y2) as i32;
         let ey = max(self.y1, self.y2) as i32;
         for x in sx..ex {
-            fb.poke(x, sy, self.color.red, self.color.green, self.color.blue, 1.0);
-            fb.poke(x, ey, self.color.red, self.color.green, self.color.blue, 1.0);
+            fb.poke(x, sy, self.color.red, self.color.green, self.color.blue, self.color.opacity());
+            fb.poke(x, ey, self.color.red, self.color.green, self.color.blue, self.color.opacity());
         }       
         for y in sy..ey {
-            fb.poke(sx, y, self.color.red, self.color.green, self.color.blue, 1.0);
-            fb.poke(ex, y, self.color.red, self.color.green, self.color.blue, 1.0);
+            fb.poke(sx, y, self.color.red, self.color.green, self.color.blue, self.color.opacity());
+            fb.poke(ex, y, self.color.red, self.color.green, self.color.blue, self.color.opacity());
         } 
     }
 }
\ No newline at end of file
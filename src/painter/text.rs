@@ -1,4 +1,4 @@
-use crate::{fonts::Font, framebuffer::{Color, FrameBuffer}};
+use crate::{color::Color, fonts::Font, framebuffer::FrameBuffer};
 
 use super::PaintAction;
 
@@ -9,12 +9,19 @@ pub struct WriteText {
     font: Font,
     color: Color,
     x: u32,
-    y: u32
+    y: u32,
+    fade: Option<(u32, u32)>
 }
 
 impl WriteText {
     pub fn new(t: &str, f: &Font, c: &Color, x: u32, y: u32) -> Self {
-        WriteText { text: t.to_string(), font: f.clone(), color: c.clone(), x, y }
+        WriteText { text: t.to_string(), font: f.clone(), color: c.clone(), x, y, fade: None }
+    }
+
+    /// Fades the text out from `from_x` on until it is gone at `to_x`.
+    pub fn with_fade(mut self, from_x: u32, to_x: u32) -> Self {
+        self.fade = Some((from_x, to_x));
+        self
     }
 }
 
@@ -23,8 +30,12 @@ impl PaintAction for WriteText {
         self.font.draw(&self.text, |x, y, v| {
             let px = self.x as i32 + x;
             let py = self.y as i32 + y;
-            if v > 0.5 {
-                fb.poke(px, py, self.color.red, self.color.green, self.color.blue, v);
+            let fade = match self.fade {
+                Some((from, to)) if px > from as i32 => (to as i32 - px) as f32 / (to - from).max(1) as f32,
+                _ => 1.0
+            };
+            if v > 0.5 && fade > 0.0 {
+                fb.poke(px, py, self.color.red, self.color.green, self.color.blue, v * self.color.opacity() * fade.min(1.0));
             }
         });
     }
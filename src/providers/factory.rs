@@ -1,40 +1,73 @@
+use std::rc::Rc;
 
 use serde_json::Value;
 
-use crate::utils::{list_folder_configs, load_config};
+use crate::{error::{Result, ScreenError}, scheduler::Schedule, settings::Settings, utils::ConfigFolder};
 
 use super::{openweather::OpenWeather, openweatherforecast::OpenWeatherForecast, rss::RSSProvider, NoopProvider, Provider};
 
 pub struct ProviderFactory {
-    folder: String
+    configs: ConfigFolder,
+    settings: Rc<Settings>
 }
 
 impl ProviderFactory {
-    pub fn new(path: &str) -> Self {
-        ProviderFactory { folder: path.to_string() }
+    pub fn new(path: &str, settings: Rc<Settings>) -> Self {
+        ProviderFactory { configs: ConfigFolder::new(path), settings }
     }
 
-    pub fn list_providers(&self) -> Vec<String> {
-        list_folder_configs(&self.folder)
+    pub fn list_providers(&self) -> Result<Vec<String>> {
+        self.configs.list()
     }
 
-    pub fn load_provider(&self, name: &str) -> Box<dyn Provider> {
-        let config = load_config(&self.folder, name);
-        let provider = self.create(name, config);
-        provider
+    pub fn load_provider(&self, name: &str) -> Result<(Box<dyn Provider>, Schedule)> {
+        let config = self.configs.get(name)?;
+        let schedule = Schedule::new(&config, self.settings.clone())?;
+        Ok((self.create(name, config)?, schedule))
     }
 
-    fn create(&self, name: &str, value: Value) -> Box<dyn Provider> {
+    /// From now on providers are only re-read through `reload`, which keeps the last good version.
+    pub fn keep_last_good(&mut self) {
+        self.configs.keep_last_good();
+        for name in self.list_providers().unwrap_or_default() {
+            if let Err(e) = self.reload(&name) {
+                log::error!("Provider {} is broken: {}", name, e);
+            }
+        }
+    }
+
+    /// Re-reads a provider config and swaps it in only if it is valid.
+    pub fn reload(&mut self, name: &str) -> Result<()> {
+        match self.configs.read(name)? {
+            Some(config) => {
+                self.check(name, &config)?;
+                self.configs.keep(name, config);
+            },
+            None => self.configs.forget(name)
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self, name: &str) -> Result<()> {
+        self.check(name, &self.configs.get(name)?)
+    }
+
+    fn check(&self, name: &str, config: &Value) -> Result<()> {
+        Schedule::new(config, self.settings.clone())?;
+        self.create(name, config.clone()).map(|_| ())
+    }
+
+    fn create(&self, name: &str, value: Value) -> Result<Box<dyn Provider>> {
         match value["type"].as_str() {
             Some(t) => {
                 match t {
-                    "rss" => Box::new(RSSProvider::new(name, &value)),
-                    "openweather" => Box::new(OpenWeather::new(name, &value)),
-                    "openweatherforecast" => Box::new(OpenWeatherForecast::new(name, &value)),
-                    _ => Box::new(NoopProvider::new())
+                    "rss" => Ok(Box::new(RSSProvider::new(name, &value))),
+                    "openweather" => Ok(Box::new(OpenWeather::new(name, &value))),
+                    "openweatherforecast" => Ok(Box::new(OpenWeatherForecast::new(name, &value))),
+                    _ => Err(ScreenError::Config(format!("unknown provider type \"{}\"", t)))
                 }
             }
-            None => Box::new(NoopProvider::new())
+            None => Ok(Box::new(NoopProvider::new()))
         }
     }
-}
\ No newline at end of file
+}
@@ -1,12 +1,13 @@
-use crate::data::DataStore;
+use crate::{data::DataStore, error::Result};
 
 pub mod factory;
+pub mod pool;
 pub mod rss;
 pub mod openweather;
 pub mod openweatherforecast;
 
-pub trait Provider {
-    fn provide(&mut self, data_store: &mut DataStore);
+pub trait Provider: Send {
+    fn provide(&mut self, data_store: &mut DataStore) -> Result<()>;
 }
 
 
@@ -20,6 +21,7 @@ impl NoopProvider {
 }
 
 impl Provider for NoopProvider {
-    fn provide(&mut self, _data_store: &mut DataStore) {
+    fn provide(&mut self, _data_store: &mut DataStore) -> Result<()> {
+        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,56 @@
+use std::{sync::{mpsc::{self, Sender}, Arc, Mutex}, thread};
+
+use crate::{data::DataStore, error::Result};
+
+use super::Provider;
+
+struct Job {
+    name: String,
+    provider: Box<dyn Provider>
+}
+
+/// Sent back by a worker once a provider has finished.
+pub struct Finished {
+    pub name: String,
+    pub result: Result<()>
+}
+
+/// Runs providers on a fixed set of worker threads so slow fetches never hold up
+/// the display. Each result is sent to `events` as it comes in.
+pub struct ProviderPool {
+    jobs: Sender<Job>
+}
+
+impl ProviderPool {
+    pub fn new<T>(threads: usize, data_store: &DataStore, events: Sender<T>) -> Self
+    where T: From<Finished> + Send + 'static {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        for i in 0..threads.max(1) {
+            let job_receiver = job_receiver.clone();
+            let events = events.clone();
+            let mut data_store = data_store.clone();
+            let spawned = thread::Builder::new().name(format!("provider-{}", i)).spawn(move || loop {
+                let job = match job_receiver.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(_) => return
+                };
+                let Ok(mut job) = job else {
+                    return;
+                };
+                let result = job.provider.provide(&mut data_store);
+                if events.send(T::from(Finished { name: job.name, result })).is_err() {
+                    return;
+                }
+            });
+            if let Err(e) = spawned {
+                log::error!("Cannot start provider thread: {}", e);
+            }
+        }
+        ProviderPool { jobs }
+    }
+
+    pub fn submit(&self, name: &str, provider: Box<dyn Provider>) {
+        let _ = self.jobs.send(Job { name: name.to_string(), provider });
+    }
+}
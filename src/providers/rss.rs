@@ -1,10 +1,10 @@
 
-use std::io::Read;
+use std::{io::Read, time::Duration};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::{data::DataStore, models::news::{ArticleData, ChannelData, ImageData, RssData}};
+use crate::{data::DataStore, error::{Result, ScreenError}, models::news::{ArticleData, ChannelData, ImageData, RssData}, utils::http_get};
 
 use super::Provider;
 
@@ -48,54 +48,52 @@ struct XmlMediaGroupData {
 pub struct RSSProvider {
     name: String,
     url: String,
+    timeout: Duration
 }
 
 impl RSSProvider {
     pub fn new(name: &str, value: &Value) -> Self {
-        let url = match value["url"].as_str() {
-            Some(url_str) => url_str,
-            None => "https://feeds.bbci.co.uk/news/world/rss.xml"
-        };
-        RSSProvider { name: name.to_string(), url: url.to_string() }
+        let url = value["url"].as_str().unwrap_or("https://feeds.bbci.co.uk/news/world/rss.xml");
+        let timeout = Duration::from_secs(value["timeout"].as_u64().unwrap_or(30));
+        RSSProvider { name: name.to_string(), url: url.to_string(), timeout }
     }
 }
 
 impl Provider for RSSProvider {
-    fn provide(&mut self, data_store: &mut DataStore) {
-        if let Ok(mut res) =  reqwest::blocking::get(&self.url) {
-            let mut body_str = String::new();
-            res.read_to_string(&mut body_str).unwrap();
-            let xml_rss: XmlRssData = serde_xml_rs::from_str(&body_str).unwrap();
-            let rss = RssData {
-                channels: xml_rss.channel.iter().map(|xml_chan| {
-                    ChannelData {
-                        title: xml_chan.title.clone(),
-                        image: ImageData { 
-                            url: xml_chan.image.url.clone() 
-                        },
-                        items: xml_chan.item.iter().map(|item| {
-                            ArticleData { 
-                                title: item.title.clone(), 
-                                description: item.description.clone(),
-                                image: ImageData { 
-                                    url: if let Some(img) = &item.image1 {
-                                        img.url.clone()
-                                    } else if let Some(img) = &item.image2 {
-                                        img.url.clone()
-                                    } else if let Some(img) = &item.image3 {
-                                        img.image[0].url.clone()
-                                    } else {
-                                        "".to_owned()
-                                    }
-                                } 
-                            }
-                        }).collect()
-                    }
-                }).collect()
-            };
+    fn provide(&mut self, data_store: &mut DataStore) -> Result<()> {
+        let mut res = http_get(&self.url, self.timeout)?;
+        let mut body_str = String::new();
+        res.read_to_string(&mut body_str).map_err(|e| ScreenError::http(&self.url, e))?;
+        let xml_rss: XmlRssData = serde_xml_rs::from_str(&body_str).map_err(|e| ScreenError::http(&self.url, e))?;
+        let rss = RssData {
+            channels: xml_rss.channel.iter().map(|xml_chan| {
+                ChannelData {
+                    title: xml_chan.title.clone(),
+                    image: ImageData { 
+                        url: xml_chan.image.url.clone() 
+                    },
+                    items: xml_chan.item.iter().map(|item| {
+                        ArticleData { 
+                            title: item.title.clone(), 
+                            description: item.description.clone(),
+                            image: ImageData { 
+                                url: if let Some(img) = &item.image1 {
+                                    img.url.clone()
+                                } else if let Some(img) = &item.image2 {
+                                    img.url.clone()
+                                } else if let Some(img) = item.image3.as_ref().and_then(|group| group.image.first()) {
+                                    img.url.clone()
+                                } else {
+                                    "".to_owned()
+                                }
+                            } 
+                        }
+                    }).collect()
+                }
+            }).collect()
+        };
 
-            let json_str = serde_json::to_string_pretty(&rss).unwrap();
-            data_store.store(&self.name, &json_str); 
-        }        
+        let json_str = serde_json::to_string_pretty(&rss).map_err(|e| ScreenError::json(&self.name, e))?;
+        data_store.store(&self.name, &json_str)
     }
 }
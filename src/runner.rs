@@ -1,55 +1,352 @@
-use std::{collections::HashMap, thread::sleep, time::Duration};
+use std::{rc::Rc, sync::mpsc::{self, Receiver, RecvTimeoutError, Sender}, thread::sleep, time::{Duration, Instant, SystemTime}};
 
-use datetime::LocalDateTime;
+use crate::{components::{factory::PageFactory, Page}, data::DataStore, error::Result, fonts::{font_dirs, FontFactory}, framebuffer::FrameBuffer, layout::LayoutItem, output::DisplaySink, providers::{factory::ProviderFactory, pool::{Finished, ProviderPool}}, scheduler::Scheduler, settings::Settings, watcher::{self, Change, ChangeKind}};
+/// How often providers are checked for being due while a page is up.
+const PROVIDER_POLL: Duration = Duration::from_secs(5);
+const PROVIDER_THREADS: usize = 4;
+
+enum Event {
+    ProviderFinished(Finished),
+    Changed(Change)
+}
+
+impl From<Finished> for Event {
+    fn from(finished: Finished) -> Self {
+        Event::ProviderFinished(finished)
+    }
+}
+
+impl From<Change> for Event {
+    fn from(change: Change) -> Self {
+        Event::Changed(change)
+    }
+}
 
-use crate::{components::factory::PageFactory, data::DataStore, framebuffer::FrameBuffer, providers::factory::ProviderFactory};
 pub struct Runner {
+    root: String,
+    settings: Rc<Settings>,
     page_factory: PageFactory,
     provider_factory: ProviderFactory,
-    providers_next_run: HashMap<String, LocalDateTime>,
-    data_store: DataStore
+    scheduler: Scheduler,
+    pool: ProviderPool,
+    data_store: DataStore,
+    sender: Sender<Event>,
+    events: Receiver<Event>,
+    watching: bool,
+    follow_edits: bool
 }
 
 impl Runner {
-    pub fn new() -> Self {
-        let page_factory = PageFactory::new("./pages");
-        let provider_factory = ProviderFactory::new("./providers");
-        let data_store = DataStore::new("./data");
-        let providers_next_run: HashMap<String, LocalDateTime> = HashMap::new();
-        Runner{page_factory, provider_factory, providers_next_run, data_store}
+    /// Reads pages, providers and data from folders under `root`.
+    pub fn new(root: &str, settings: Rc<Settings>) -> Self {
+        let font_factory = FontFactory::new(&font_dirs(root, &settings.font_dirs));
+        let page_factory = PageFactory::new(&format!("{}/pages", root), &format!("{}/themes", root), Rc::new(font_factory), settings.clone());
+        let provider_factory = ProviderFactory::new(&format!("{}/providers", root), settings.clone());
+        let data_store = DataStore::new(&format!("{}/data", root));
+        let scheduler = Scheduler::new();
+        let (sender, events) = mpsc::channel();
+        let pool = ProviderPool::new(PROVIDER_THREADS, &data_store, sender.clone());
+        Runner{root: root.to_string(), settings, page_factory, provider_factory, scheduler, pool, data_store, sender, events, watching: false, follow_edits: false}
+    }
+
+    /// Jump straight to a page as soon as its config is saved.
+    pub fn set_follow_edits(&mut self, follow_edits: bool) {
+        self.follow_edits = follow_edits;
+    }
+
+    /// Watches the config folders so edits are picked up while running.
+    fn start_watching(&mut self) {
+        let pages = format!("{}/pages", self.root);
+        let providers = format!("{}/providers", self.root);
+        let data = format!("{}/data", self.root);
+        let folders = [(pages.as_str(), ChangeKind::Page), (providers.as_str(), ChangeKind::Provider), (data.as_str(), ChangeKind::Data)];
+        match watcher::watch(&folders, self.sender.clone()) {
+            Ok(()) => {
+                self.page_factory.keep_last_good();
+                self.provider_factory.keep_last_good();
+                self.watching = true;
+            },
+            Err(e) => log::warn!("Not watching configs for changes: {}", e)
+        }
+    }
+
+    pub fn run(&mut self, sink: &mut dyn DisplaySink) {
+        let (width, height) = sink.canvas_size();
+        let mut frame_buffer = self.frame_buffer(width, height);
+        let mut next_frame_buffer = self.frame_buffer(width, height);
+        self.start_watching();
+        let mut jump = None;
+        loop {
+            let page_list = match self.page_names() {
+                Ok(page_list) => page_list,
+                Err(e) => {
+                    log::error!("No pages: {}", e);
+                    sleep(Duration::from_secs(10));
+                    continue;
+                }
+            };
+            let mut next = 0;
+            while next < page_list.len() {
+                let page_name = match jump.take() {
+                    Some(page_name) => page_name,
+                    None => {
+                        next += 1;
+                        page_list[next - 1].clone()
+                    }
+                };
+                self.start_due_providers();
+                log::debug!("Showing {}", page_name);
+                let page = match self.page_factory.load_page(&page_name) {
+                    Ok(page) => page,
+                    Err(e) => {
+                        log::error!("Skipping page {}: {}", page_name, e);
+                        continue;
+                    }
+                };
+                let mut layout = page.layout(&self.data_store, width, height);
+                next_frame_buffer.set_background(page.background.clone());
+                next_frame_buffer.clear();
+                Page::paint(layout.as_ref()).paint_on(&mut next_frame_buffer);
+                page.transition.run(&frame_buffer, &next_frame_buffer, sink);
+                sink.show(&next_frame_buffer);
+                std::mem::swap(&mut frame_buffer, &mut next_frame_buffer);
+                let until = Instant::now() + Duration::from_secs(page.seconds as u64);
+                jump = self.keep_page(&page_name, &page, &mut layout, &mut frame_buffer, sink, until);
+            }
+        }
+
+    }
+
+    /// Hands every provider that is due to the worker pool.
+    fn start_due_providers(&mut self) {
+        let provider_list = self.provider_factory.list_providers().unwrap_or_else(|e| {
+            log::warn!("No providers: {}", e);
+            vec![]
+        });
+        for provider_name in provider_list  {
+            let (provider, schedule) = match self.provider_factory.load_provider(&provider_name) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    log::error!("Cannot load provider {}: {}", provider_name, e);
+                    continue;
+                }
+            };
+            if self.scheduler.is_due(&provider_name, &schedule) {
+                self.scheduler.started(&provider_name, &schedule);
+                self.pool.submit(&provider_name, provider);
+            }
+            // Outside its hours the provider can't refresh, so the last data stays up until it runs again
+            if let (Some(max), Some(age)) = (schedule.max_staleness, self.data_store.age(&provider_name)) {
+                if age > max && schedule.is_active() {
+                    log::warn!("Data of {} is {}s old, dropping it", provider_name, age.as_secs());
+                    if let Err(e) = self.data_store.invalidate(&provider_name) {
+                        log::error!("{}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records the outcome of a provider run. Returns true if it stored fresh data.
+    fn provider_finished(&mut self, finished: Finished) -> bool {
+        match finished.result {
+            Ok(()) => {
+                self.scheduler.succeeded(&finished.name);
+                true
+            },
+            Err(e) => {
+                let retry = self.scheduler.failed(&finished.name);
+                log::error!("Provider {} failed, retrying in {:.0}s: {}", finished.name, retry.as_secs_f32(), e);
+                false
+            }
+        }
     }
 
-    pub fn run(&mut self, frame_buffer: &mut FrameBuffer) {
+    /// Keeps the page up until `until`. Parts of it that refresh on their own are repainted
+    /// in place, and the whole page is produced again when data it shows is updated.
+    /// Returns the page to show next if an edit calls for it.
+    fn keep_page(&mut self, name: &str, page: &Page, layout: &mut Box<dyn LayoutItem>, frame_buffer: &mut FrameBuffer, sink: &mut dyn DisplaySink, until: Instant) -> Option<String> {
+        let mut next_poll = Instant::now() + PROVIDER_POLL;
         loop {
-            let now = LocalDateTime::now();
-            let provider_list = self.provider_factory.list_providers();
-            for provider_name in provider_list  {
-                let next_run = self.providers_next_run.get(&provider_name).unwrap_or(&now);
-                if next_run <= &now {
-                    let mut provider = self.provider_factory.load_provider(&provider_name);
-                    provider.provide(&mut self.data_store);
-                    let _ = self.providers_next_run.insert(provider_name, now.add_seconds(300));
+            let now = Instant::now();
+            if now >= until {
+                return None;
+            }
+            if now >= next_poll {
+                self.start_due_providers();
+                next_poll = now + PROVIDER_POLL;
+            }
+            let mut wait = until.min(next_poll) - now;
+            if let Some(next) = layout.next_refresh() {
+                wait = wait.min(next.duration_since(SystemTime::now()).unwrap_or_default());
+            }
+            let updated = match self.events.recv_timeout(wait) {
+                Ok(Event::ProviderFinished(finished)) => {
+                    let data_name = finished.name.clone();
+                    // While watching, the data file being written reports the update
+                    (self.provider_finished(finished) && !self.watching).then_some(data_name)
+                },
+                Ok(Event::Changed(Change { kind: ChangeKind::Data, name: data_name })) => Some(data_name),
+                Ok(Event::Changed(change)) => {
+                    let jump = self.config_changed(change, name);
+                    if jump.is_some() {
+                        return jump;
+                    }
+                    None
+                },
+                Err(RecvTimeoutError::Timeout) => {
+                    self.refresh_live(layout.as_mut(), frame_buffer, sink);
+                    None
+                },
+                Err(RecvTimeoutError::Disconnected) => {
+                    sleep(wait);
+                    None
                 }
+            };
+            if let Some(data_name) = updated.filter(|data_name| page.depends_on(data_name)) {
+                log::debug!("New data from {}, producing page again", data_name);
+                *layout = page.layout(&self.data_store, frame_buffer.width(), frame_buffer.height());
+                frame_buffer.clear();
+                Page::paint(layout.as_ref()).paint_on(frame_buffer);
+                sink.show(frame_buffer);
             }
+        }
+    }
+
+    /// Reloads an edited page or provider config. Returns the page to show right away:
+    /// the current one when it was edited, or any edited page when following edits.
+    fn config_changed(&mut self, change: Change, current: &str) -> Option<String> {
+        match change.kind {
+            ChangeKind::Page => match self.page_factory.reload(&change.name) {
+                Ok(()) => {
+                    log::info!("Reloaded page {}", change.name);
+                    if change.name == current || self.follow_edits {
+                        return Some(change.name);
+                    }
+                },
+                Err(e) => log::error!("Page {} is broken, keeping last good version: {}", change.name, e)
+            },
+            ChangeKind::Provider => match self.provider_factory.reload(&change.name) {
+                Ok(()) => {
+                    log::info!("Reloaded provider {}", change.name);
+                    self.scheduler.reset(&change.name);
+                    self.start_due_providers();
+                },
+                Err(e) => log::error!("Provider {} is broken, keeping last good version: {}", change.name, e)
+            },
+            ChangeKind::Data => {}
+        }
+        None
+    }
 
-            let mut page_list = self.page_factory.list_pages();
-            page_list.sort();
-            for page_name in page_list {
-                //println!("Showing {}", name);
-                let page = self.page_factory.load_page(&page_name);
+    /// Repaints the parts of the page that refresh on their own and are due.
+    fn refresh_live(&self, layout: &mut dyn LayoutItem, frame_buffer: &mut FrameBuffer, sink: &mut dyn DisplaySink) {
+        let mut dirty = vec![];
+        if layout.refresh_live(&self.data_store, SystemTime::now(), &mut dirty) {
+            if dirty.is_empty() {
+                return;
+            }
+            let painter = Page::paint(layout);
+            for rect in dirty {
+                frame_buffer.set_clip(Some(rect));
                 frame_buffer.clear();
-                page.produce(&self.data_store).paint_on(frame_buffer);
-                frame_buffer.send();
-                sleep(Duration::from_secs(page.seconds as u64));
+                painter.paint_on(frame_buffer);
             }
+            frame_buffer.set_clip(None);
+        } else {
+            layout.run_layout_top_down(frame_buffer.width(), frame_buffer.height());
+            layout.run_layout_position(0, 0);
+            frame_buffer.clear();
+            Page::paint(layout).paint_on(frame_buffer);
         }
+        sink.show(frame_buffer);
+    }
+
+    pub fn save_page(&self, page: &str, sink: &mut dyn DisplaySink) -> Result<()> {
+        log::info!("Saving page {}", page);
+        let (width, height) = sink.canvas_size();
+        let mut frame_buffer = FrameBuffer::new(width, height);
+        let page = self.page_factory.load_page(page)?;
+        frame_buffer.set_background(page.background.clone());
+        frame_buffer.clear();
+        page.produce(&self.data_store, frame_buffer.width(), frame_buffer.height()).paint_on(&mut frame_buffer);
+        sink.show(&frame_buffer);
+        log::info!("Wrote {} bytes", sink.stats().last_frame_bytes);
+        Ok(())
+    }
+
+    /// A frame buffer cleared to the background from the settings.
+    fn frame_buffer(&self, width: u32, height: u32) -> FrameBuffer {
+        let mut frame_buffer = FrameBuffer::new(width, height);
+        frame_buffer.set_background(self.settings.background.clone());
+        frame_buffer.clear();
+        frame_buffer
+    }
+
+    pub fn page_names(&self) -> Result<Vec<String>> {
+        let mut page_list = self.page_factory.list_pages()?;
+        page_list.sort();
+        Ok(page_list)
+    }
 
+    /// Runs one provider right away, regardless of its schedule.
+    pub fn fetch(&self, provider: &str) -> Result<()> {
+        let (mut provider, _) = self.provider_factory.load_provider(provider)?;
+        let mut data_store = self.data_store.clone();
+        provider.provide(&mut data_store)
     }
 
-    pub fn save_page(&self, page: &str, frame_buffer: &mut FrameBuffer) {
-        println!("Saving page {}", page);
-        let page = self.page_factory.load_page(page);
-        page.produce(&self.data_store).paint_on(frame_buffer);
-        frame_buffer.save_png("out.png");
+    /// Checks every page and provider, logging the broken ones. Returns true if all are fine.
+    pub fn validate(&self) -> bool {
+        let mut valid = true;
+        for name in self.page_factory.list_pages().unwrap_or_else(|e| { log::error!("No pages: {}", e); vec![] }) {
+            match self.page_factory.validate(&name) {
+                Ok(()) => log::info!("Page {} is fine", name),
+                Err(e) => {
+                    log::error!("Page {} is broken: {}", name, e);
+                    valid = false;
+                }
+            }
+        }
+        for name in self.provider_factory.list_providers().unwrap_or_else(|e| { log::warn!("No providers: {}", e); vec![] }) {
+            match self.provider_factory.validate(&name) {
+                Ok(()) => log::info!("Provider {} is fine", name),
+                Err(e) => {
+                    log::error!("Provider {} is broken: {}", name, e);
+                    valid = false;
+                }
+            }
+        }
+        valid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, rc::Rc};
+
+    use serde_json::json;
+
+    use crate::{output::{encode::Dither, memory::MemorySink}, screeninfo::{PixelFormat, ScreenInfo}, settings::Settings};
+
+    use super::Runner;
+
+    #[test]
+    fn renders_page_into_memory() {
+        let root = std::env::temp_dir().join(format!("screen-runner-{}", std::process::id()));
+        fs::create_dir_all(root.join("pages")).unwrap();
+        fs::write(root.join("pages/blank.json"), r#"{"component": {"type": "container"}}"#).unwrap();
+        let settings = Settings::new(&json!({"background": "#102030"})).unwrap();
+        let runner = Runner::new(root.to_str().unwrap(), Rc::new(settings));
+        let mut sink = MemorySink::new(ScreenInfo::from_format(4, 2, PixelFormat::Xrgb8888), Dither::None);
+        runner.save_page("blank", &mut sink).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(sink.frames().len(), 1);
+        let frame = &sink.frames()[0];
+        assert_eq!(frame.len(), 4 * 2 * 4);
+        for pixel in frame.chunks(4) {
+            assert_eq!(&pixel[..3], &[0x30, 0x20, 0x10]);
+        }
     }
-}
\ No newline at end of file
+}
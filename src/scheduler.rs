@@ -0,0 +1,120 @@
+use std::{collections::HashMap, rc::Rc, time::{Duration, Instant}};
+
+use chrono::{Datelike, Weekday};
+use rand::Rng;
+use serde_json::Value;
+
+use crate::{error::{Result, ScreenError}, settings::Settings, utils::TimeRange};
+
+/// When a provider runs, read from its config:
+/// `"interval": 600` seconds between fetches, `provider_interval` from the settings if not given,
+/// `"retry": 30` and `"retry_max": 600` bound the backoff after failures,
+/// `"max_staleness": 3600` drops the stored data once it is older than that, while the provider may run,
+/// `"between": "06:00-23:00"` and `"days": ["mon", "tue"]` limit when it may run at all,
+/// in the timezone from the settings.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    interval: Duration,
+    retry: Duration,
+    retry_max: Duration,
+    pub max_staleness: Option<Duration>,
+    between: Option<TimeRange>,
+    days: Option<Vec<Weekday>>,
+    settings: Rc<Settings>
+}
+
+impl Schedule {
+    pub fn new(value: &Value, settings: Rc<Settings>) -> Result<Self> {
+        let interval = Duration::from_secs(value["interval"].as_u64().unwrap_or(settings.provider_interval).max(1));
+        let retry = Duration::from_secs(value["retry"].as_u64().unwrap_or(30).max(1));
+        let retry_max = value["retry_max"].as_u64().map(Duration::from_secs).unwrap_or(interval).max(retry);
+        let max_staleness = value["max_staleness"].as_u64().map(Duration::from_secs);
+        let between = match value["between"].as_str() {
+            Some(between) => Some(TimeRange::parse(between)?),
+            None => None
+        };
+        let days = match value["days"].as_array() {
+            Some(days) => Some(days.iter()
+                .map(|day| day.as_str().and_then(|d| d.parse::<Weekday>().ok())
+                    .ok_or_else(|| ScreenError::Config(format!("invalid day {}", day))))
+                .collect::<Result<Vec<Weekday>>>()?),
+            None => None
+        };
+        Ok(Schedule { interval, retry, retry_max, max_staleness, between, days, settings })
+    }
+
+    /// Whether the current local time falls inside the configured days and hours.
+    pub fn is_active(&self) -> bool {
+        let now = self.settings.now();
+        if let Some(days) = &self.days {
+            if !days.contains(&now.weekday()) {
+                return false;
+            }
+        }
+        self.between.as_ref().is_none_or(|between| between.contains(now.time()))
+    }
+}
+
+struct ProviderState {
+    next_run: Instant,
+    failures: u32,
+    running: Option<Schedule>
+}
+
+/// Keeps track of when each provider is due, backing off exponentially while it fails.
+pub struct Scheduler {
+    states: HashMap<String, ProviderState>
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Scheduler { states: HashMap::new() }
+    }
+
+    pub fn is_due(&self, name: &str, schedule: &Schedule) -> bool {
+        let due = match self.states.get(name) {
+            Some(state) => state.running.is_none() && state.next_run <= Instant::now(),
+            None => true
+        };
+        due && schedule.is_active()
+    }
+
+    /// Marks the provider as running so it isn't started again before it finishes.
+    pub fn started(&mut self, name: &str, schedule: &Schedule) {
+        let state = self.states.entry(name.to_string()).or_insert(ProviderState { next_run: Instant::now(), failures: 0, running: None });
+        state.running = Some(schedule.clone());
+    }
+
+    pub fn succeeded(&mut self, name: &str) {
+        if let Some(state) = self.states.get_mut(name) {
+            if let Some(schedule) = state.running.take() {
+                state.next_run = Instant::now() + schedule.interval;
+                state.failures = 0;
+            }
+        }
+    }
+
+    /// Makes the provider due right away, e.g. after its config was edited.
+    pub fn reset(&mut self, name: &str) {
+        if let Some(state) = self.states.get_mut(name) {
+            state.next_run = Instant::now();
+            state.failures = 0;
+        }
+    }
+
+    /// Returns how long until the next attempt.
+    pub fn failed(&mut self, name: &str) -> Duration {
+        let Some(state) = self.states.get_mut(name) else {
+            return Duration::ZERO;
+        };
+        let Some(schedule) = state.running.take() else {
+            return Duration::ZERO;
+        };
+        state.failures += 1;
+        let backoff = schedule.retry.saturating_mul(1 << (state.failures - 1).min(16)).min(schedule.retry_max);
+        // Spread retries out so providers that failed together don't retry together
+        let delay = backoff.mul_f32(rand::rng().random_range(0.8..1.2));
+        state.next_run = Instant::now() + delay;
+        delay
+    }
+}
@@ -0,0 +1,306 @@
+use std::{fs::File, io, os::fd::AsRawFd};
+
+use libc::{c_char, c_ulong};
+
+const FBIOGET_VSCREENINFO: c_ulong = 0x4600;
+const FBIOPUT_VSCREENINFO: c_ulong = 0x4601;
+const FBIOGET_FSCREENINFO: c_ulong = 0x4602;
+const FBIOPAN_DISPLAY: c_ulong = 0x4606;
+const FBIO_WAITFORVSYNC: c_ulong = 0x40044620;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct FbBitfield {
+    offset: u32,
+    length: u32,
+    msb_right: u32
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct FbVarScreenInfo {
+    xres: u32,
+    yres: u32,
+    xres_virtual: u32,
+    yres_virtual: u32,
+    xoffset: u32,
+    yoffset: u32,
+    bits_per_pixel: u32,
+    grayscale: u32,
+    red: FbBitfield,
+    green: FbBitfield,
+    blue: FbBitfield,
+    transp: FbBitfield,
+    nonstd: u32,
+    activate: u32,
+    height: u32,
+    width: u32,
+    accel_flags: u32,
+    pixclock: u32,
+    left_margin: u32,
+    right_margin: u32,
+    upper_margin: u32,
+    lower_margin: u32,
+    hsync_len: u32,
+    vsync_len: u32,
+    sync: u32,
+    vmode: u32,
+    rotate: u32,
+    colorspace: u32,
+    reserved: [u32; 4]
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct FbFixScreenInfo {
+    id: [c_char; 16],
+    smem_start: c_ulong,
+    smem_len: u32,
+    fb_type: u32,
+    type_aux: u32,
+    visual: u32,
+    xpanstep: u16,
+    ypanstep: u16,
+    ywrapstep: u16,
+    line_length: u32,
+    mmio_start: c_ulong,
+    mmio_len: u32,
+    accel: u32,
+    capabilities: u16,
+    reserved: [u16; 2]
+}
+
+fn get_var_screen_info(file: &File) -> io::Result<FbVarScreenInfo> {
+    let mut var = FbVarScreenInfo::default();
+    if unsafe { libc::ioctl(file.as_raw_fd(), FBIOGET_VSCREENINFO as _, &mut var as *mut FbVarScreenInfo) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(var)
+}
+
+fn get_fix_screen_info(file: &File) -> io::Result<FbFixScreenInfo> {
+    let mut fix = FbFixScreenInfo::default();
+    if unsafe { libc::ioctl(file.as_raw_fd(), FBIOGET_FSCREENINFO as _, &mut fix as *mut FbFixScreenInfo) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(fix)
+}
+
+/// The display mode as the driver had it, to put back when done.
+pub struct SavedMode(FbVarScreenInfo);
+
+pub fn save_mode(file: &File) -> io::Result<SavedMode> {
+    get_var_screen_info(file).map(SavedMode)
+}
+
+pub fn restore_mode(file: &File, mode: &SavedMode) -> io::Result<()> {
+    let mut var = mode.0;
+    if unsafe { libc::ioctl(file.as_raw_fd(), FBIOPUT_VSCREENINFO as _, &mut var as *mut FbVarScreenInfo) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Makes the virtual framebuffer twice the visible height so frames can be drawn
+/// off-screen and flipped in by panning. Returns false if the driver can't do it.
+pub fn setup_double_buffer(file: &File) -> io::Result<bool> {
+    let fix = get_fix_screen_info(file)?;
+    if fix.ypanstep == 0 {
+        return Ok(false);
+    }
+    let mut var = get_var_screen_info(file)?;
+    if var.yres_virtual < 2 * var.yres {
+        var.yres_virtual = 2 * var.yres;
+        var.xoffset = 0;
+        var.yoffset = 0;
+        if unsafe { libc::ioctl(file.as_raw_fd(), FBIOPUT_VSCREENINFO as _, &mut var as *mut FbVarScreenInfo) } < 0 {
+            return Ok(false);
+        }
+        var = get_var_screen_info(file)?;
+    }
+    let fix = get_fix_screen_info(file)?;
+    Ok(var.yres_virtual >= 2 * var.yres && fix.smem_len >= 2 * var.yres * fix.line_length)
+}
+
+pub fn pan_display(file: &File, yoffset: u32) -> io::Result<()> {
+    let mut var = get_var_screen_info(file)?;
+    var.xoffset = 0;
+    var.yoffset = yoffset;
+    if unsafe { libc::ioctl(file.as_raw_fd(), FBIOPAN_DISPLAY as _, &mut var as *mut FbVarScreenInfo) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub fn wait_for_vsync(file: &File) -> io::Result<()> {
+    let mut screen: u32 = 0;
+    if unsafe { libc::ioctl(file.as_raw_fd(), FBIO_WAITFORVSYNC as _, &mut screen as *mut u32) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bitfield {
+    pub offset: u32,
+    pub length: u32
+}
+
+impl Bitfield {
+    pub fn new(offset: u32, length: u32) -> Self {
+        Bitfield { offset, length }
+    }
+
+    /// The largest value the channel holds, `length` low bits set.
+    fn mask(&self) -> u32 {
+        u32::MAX.checked_shr(32 - self.length.min(32)).unwrap_or(0)
+    }
+
+    fn pack(&self, v: u8) -> u32 {
+        if self.length == 0 {
+            return 0;
+        }
+        let c = if self.length >= 8 {
+            (v as u32) << (self.length - 8)
+        } else {
+            ((v as u32 * self.mask()) + 127) / 255
+        };
+        c << self.offset
+    }
+
+    fn unpack(&self, p: u32) -> u8 {
+        if self.length == 0 {
+            return 0;
+        }
+        let c = (p >> self.offset) & self.mask();
+        if self.length >= 8 {
+            (c >> (self.length - 8)) as u8
+        } else {
+            // Scale back up so full intensity maps to 255 whatever the channel length
+            ((c * 255 + self.mask() / 2) / self.mask()) as u8
+        }
+    }
+
+    /// The value `v` will read back as once stored in this channel.
+    pub fn quantize(&self, v: u8) -> u8 {
+        self.unpack(self.pack(v))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PixelFormat {
+    Rgb565,
+    Rgb888,
+    Xrgb8888,
+    Bgra8888,
+    Other
+}
+
+impl PixelFormat {
+    pub fn from_string(str: &str) -> Option<Self> {
+        match str.to_lowercase().as_str() {
+            "rgb565" => Some(PixelFormat::Rgb565),
+            "rgb888" => Some(PixelFormat::Rgb888),
+            "xrgb8888" => Some(PixelFormat::Xrgb8888),
+            "bgra8888" => Some(PixelFormat::Bgra8888),
+            _ => None
+        }
+    }
+}
+
+/// Geometry and pixel layout of a display, as reported by the fbdev driver.
+#[derive(Debug, Clone)]
+pub struct ScreenInfo {
+    pub width: u32,
+    pub height: u32,
+    pub bits_per_pixel: u32,
+    pub line_length: u32,
+    pub red: Bitfield,
+    pub green: Bitfield,
+    pub blue: Bitfield,
+    pub transp: Bitfield
+}
+
+impl ScreenInfo {
+    pub fn from_device(path: &str) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let var = get_var_screen_info(&file)?;
+        let fix = get_fix_screen_info(&file)?;
+        let bytes_per_pixel = var.bits_per_pixel.div_ceil(8);
+        let line_length = if fix.line_length > 0 { fix.line_length } else { var.xres * bytes_per_pixel };
+        Ok(ScreenInfo {
+            width: var.xres,
+            height: var.yres,
+            bits_per_pixel: var.bits_per_pixel,
+            line_length,
+            red: Bitfield::new(var.red.offset, var.red.length),
+            green: Bitfield::new(var.green.offset, var.green.length),
+            blue: Bitfield::new(var.blue.offset, var.blue.length),
+            transp: Bitfield::new(var.transp.offset, var.transp.length)
+        })
+    }
+
+    pub fn from_format(width: u32, height: u32, format: PixelFormat) -> Self {
+        let (bits_per_pixel, red, green, blue, transp) = match format {
+            PixelFormat::Rgb565 | PixelFormat::Other => (16, Bitfield::new(11, 5), Bitfield::new(5, 6), Bitfield::new(0, 5), Bitfield::new(0, 0)),
+            PixelFormat::Rgb888 => (24, Bitfield::new(16, 8), Bitfield::new(8, 8), Bitfield::new(0, 8), Bitfield::new(0, 0)),
+            PixelFormat::Xrgb8888 => (32, Bitfield::new(16, 8), Bitfield::new(8, 8), Bitfield::new(0, 8), Bitfield::new(0, 0)),
+            PixelFormat::Bgra8888 => (32, Bitfield::new(8, 8), Bitfield::new(16, 8), Bitfield::new(24, 8), Bitfield::new(0, 8)),
+        };
+        ScreenInfo { width, height, bits_per_pixel, line_length: width * (bits_per_pixel / 8), red, green, blue, transp }
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        [PixelFormat::Rgb565, PixelFormat::Rgb888, PixelFormat::Xrgb8888, PixelFormat::Bgra8888].into_iter()
+            .find(|f| {
+                let known = ScreenInfo::from_format(self.width, self.height, *f);
+                known.bits_per_pixel == self.bits_per_pixel && known.red == self.red && known.green == self.green && known.blue == self.blue
+            })
+            .unwrap_or(PixelFormat::Other)
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        self.bits_per_pixel.div_ceil(8) as usize
+    }
+
+    pub fn size(&self) -> usize {
+        (self.line_length * self.height) as usize
+    }
+
+    pub fn pixel_index(&self, x: u32, y: u32) -> usize {
+        (y * self.line_length) as usize + (x as usize * self.bytes_per_pixel())
+    }
+
+    pub fn write_pixel(&self, buf: &mut [u8], i: usize, p: u32) {
+        for (n, byte) in buf[i..i + self.bytes_per_pixel()].iter_mut().enumerate() {
+            *byte = (p >> (8 * n)) as u8;
+        }
+    }
+
+    pub fn pack(&self, r: u8, g: u8, b: u8) -> u32 {
+        self.red.pack(r) | self.green.pack(g) | self.blue.pack(b) | self.transp.pack(255)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bitfield;
+
+    #[test]
+    fn full_width_channel() {
+        let channel = Bitfield::new(0, 32);
+        assert_eq!(channel.mask(), u32::MAX);
+        assert_eq!(channel.pack(255), 255 << 24);
+        assert_eq!(channel.unpack(u32::MAX), 255);
+        assert_eq!(channel.quantize(200), 200);
+    }
+
+    #[test]
+    fn narrow_channels_round_trip_extremes() {
+        for length in 0..=32 {
+            let channel = Bitfield::new(0, length);
+            assert_eq!(channel.quantize(0), 0);
+            assert_eq!(channel.quantize(255), if length == 0 { 0 } else { 255 });
+        }
+    }
+}
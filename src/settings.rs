@@ -0,0 +1,148 @@
+use std::fs;
+
+use chrono::{DateTime, FixedOffset, Local, Locale, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::Value;
+
+use crate::{error::{Result, ScreenError}, color::Color, output::factory::spec_to_config, utils::{load_config, TimeRange}};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Units {
+    Metric,
+    Imperial
+}
+
+impl Units {
+    pub fn temperature(&self, kelvin: f64) -> String {
+        match self {
+            Units::Metric => format!("{}°C", (kelvin - 273.15).round() as i32),
+            Units::Imperial => format!("{}°F", ((kelvin - 273.15) * 1.8 + 32.0).round() as i32)
+        }
+    }
+
+    pub fn speed(&self, meters_per_second: f64) -> String {
+        match self {
+            Units::Metric => format!("{}km/h", (meters_per_second * 3.6).round() as i32),
+            Units::Imperial => format!("{}mph", (meters_per_second * 2.237).round() as i32)
+        }
+    }
+
+    pub fn pressure(&self, hectopascal: u64) -> String {
+        match self {
+            Units::Metric => format!("{}hPa", hectopascal),
+            Units::Imperial => format!("{:.2}inHg", hectopascal as f64 * 0.02953)
+        }
+    }
+}
+
+/// Display-wide defaults from `settings.json` in the root folder, e.g.
+/// `{"font": "DejaVuSans", "color": "#f0f0f0", "background": "#000000", "locale": "de_DE",
+/// "timezone": "Europe/Berlin", "units": "metric", "output": "fb:/dev/fb1", "page_seconds": 5,
+/// "provider_interval": 300, "theme": "day", "night_theme": "night", "night": "20:00-07:00",
+/// "font_dirs": ["~/fonts"], "font_fallback": ["Noto Sans CJK JP", "Noto Emoji"]}`.
+/// Everything is optional.
+#[derive(Debug)]
+pub struct Settings {
+    pub font: String,
+    pub color: Color,
+    pub secondary_color: Color,
+    pub background: Color,
+    pub locale: Locale,
+    pub timezone: Option<Tz>,
+    pub units: Units,
+    pub output: Option<Value>,
+    pub page_seconds: u32,
+    pub provider_interval: u64,
+    pub theme: Option<String>,
+    pub night_theme: Option<String>,
+    pub night: Option<TimeRange>,
+    pub font_dirs: Vec<String>,
+    pub font_fallback: Vec<String>
+}
+
+impl Settings {
+    pub fn new(value: &Value) -> Result<Self> {
+        let color = |key: &str, default: Color| match value[key].as_str() {
+            Some(color) => Color::parse(color),
+            None => Ok(default)
+        };
+        let locale = match value["locale"].as_str() {
+            Some(locale) => Locale::try_from(locale.replace('-', "_").as_str())
+                .map_err(|_| ScreenError::Config(format!("unknown locale \"{}\"", locale)))?,
+            None => Locale::POSIX
+        };
+        let timezone = match value["timezone"].as_str() {
+            Some(tz) => Some(tz.parse::<Tz>().map_err(|_| ScreenError::Config(format!("unknown timezone \"{}\"", tz)))?),
+            None => None
+        };
+        let units = match value["units"].as_str() {
+            Some("metric") | None => Units::Metric,
+            Some("imperial") => Units::Imperial,
+            Some(other) => return Err(ScreenError::Config(format!("unknown units \"{}\", expected metric or imperial", other)))
+        };
+        let night = match value["night"].as_str() {
+            Some(night) => Some(TimeRange::parse(night)?),
+            None => None
+        };
+        let output = match &value["output"] {
+            Value::String(spec) => Some(spec_to_config(spec)),
+            Value::Object(_) => Some(value["output"].clone()),
+            _ => None
+        };
+        let strings = |key: &str| value[key].as_array()
+            .map(|items| items.iter().filter_map(|i| i.as_str()).map(|i| i.to_string()).collect())
+            .unwrap_or_default();
+        Ok(Settings {
+            font: value["font"].as_str().unwrap_or("DejaVuSans").to_string(),
+            color: color("color", Color::new(240, 240, 240))?,
+            secondary_color: color("secondary_color", Color::new(200, 200, 200))?,
+            background: color("background", Color::new(0, 0, 0))?,
+            locale,
+            timezone,
+            units,
+            output,
+            page_seconds: value["page_seconds"].as_u64().unwrap_or(5) as u32,
+            provider_interval: value["provider_interval"].as_u64().unwrap_or(300),
+            theme: value["theme"].as_str().map(|s| s.to_string()),
+            night_theme: value["night_theme"].as_str().map(|s| s.to_string()),
+            night,
+            font_dirs: strings("font_dirs"),
+            font_fallback: strings("font_fallback")
+        })
+    }
+
+    /// Reads `settings.json` from `root`, falling back to the defaults if there is none.
+    pub fn load(root: &str) -> Result<Self> {
+        if !fs::exists(format!("{}/settings.json", root)).unwrap_or(false) {
+            return Settings::new(&Value::Null);
+        }
+        Settings::new(&load_config(root, "settings")?)
+    }
+
+    /// The theme for pages that don't pick one, switching to the night theme during the night.
+    pub fn current_theme(&self) -> Option<&str> {
+        if let (Some(night_theme), Some(night)) = (&self.night_theme, &self.night) {
+            if night.contains(self.now().time()) {
+                return Some(night_theme);
+            }
+        }
+        self.theme.as_deref()
+    }
+
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.local_time(Utc::now())
+    }
+
+    /// Converts to the configured timezone, or the system one if none is set.
+    pub fn local_time(&self, time: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self.timezone {
+            Some(tz) => tz.from_utc_datetime(&time.naive_utc()).fixed_offset(),
+            None => time.with_timezone(&Local).fixed_offset()
+        }
+    }
+
+    /// Formats with strftime-style `format`, naming days and months in the configured locale.
+    pub fn format_date(&self, time: &DateTime<FixedOffset>, format: &str) -> String {
+        time.format_localized(format, self.locale).to_string()
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{color::Color, error::{Result, ScreenError}, fonts::{parse_weight, Font}, layout::textbox::{Span, TextAlign, TextBox}};
+
+/// A named text style as written in a theme, e.g.
+/// `"headline": {"font": "DejaVu Sans", "weight": "bold", "italic": false, "size": 50, "color": "accent", "line_height": 1.2,
+/// "fallback": ["Noto Sans CJK JP", "Noto Emoji"], "direction": "rtl", "language": "ar", "text_align": "center"}`.
+/// Fields left out fall back to the defaults of whatever the style is used for.
+#[derive(Debug, Clone, Default)]
+pub struct StyleDef {
+    pub font: Option<String>,
+    pub weight: Option<u16>,
+    pub italic: Option<bool>,
+    pub fallback: Option<Vec<String>>,
+    pub direction: Option<String>,
+    pub language: Option<String>,
+    pub text_align: Option<String>,
+    pub size: Option<f32>,
+    pub color: Option<String>,
+    pub line_height: Option<f32>
+}
+
+impl StyleDef {
+    pub fn new(value: &Value) -> Result<Self> {
+        let font = StyleDef::font_of(value)?;
+        Ok(StyleDef {
+            size: value["size"].as_f64().map(|s| s as f32),
+            color: value["color"].as_str().map(|s| s.to_string()),
+            line_height: value["line_height"].as_f64().map(|l| l as f32),
+            ..font
+        })
+    }
+
+    /// Just the `"font"`, `"weight"`, `"italic"`, `"fallback"`, `"direction"`, `"language"` and `"text_align"`
+    /// of a config, which components take to override the font and alignment of all their styles.
+    pub fn font_of(value: &Value) -> Result<Self> {
+        let weight = match &value["weight"] {
+            Value::Null => None,
+            weight => {
+                let text = weight.as_str().map(|w| w.to_string()).unwrap_or_else(|| weight.to_string());
+                Some(parse_weight(&text).ok_or_else(|| ScreenError::Config(format!("unknown font weight {}", weight)))?)
+            }
+        };
+        Ok(StyleDef {
+            font: value["font"].as_str().map(|s| s.to_string()),
+            weight,
+            italic: value["italic"].as_bool(),
+            fallback: value["fallback"].as_array()
+                .map(|fonts| fonts.iter().filter_map(|f| f.as_str()).map(|f| f.to_string()).collect()),
+            direction: value["direction"].as_str().map(|s| s.to_string()),
+            language: value["language"].as_str().map(|s| s.to_string()),
+            text_align: value["text_align"].as_str().map(|s| s.to_string()),
+            ..Default::default()
+        })
+    }
+
+    /// The built-in style for a piece of text when the theme doesn't define one.
+    pub fn fallback(size: f32, color: &str) -> Self {
+        StyleDef { size: Some(size), color: Some(color.to_string()), ..Default::default() }
+    }
+
+    /// Fills in what this style leaves out from `other`.
+    pub fn or(self, other: &StyleDef) -> Self {
+        StyleDef {
+            font: self.font.or_else(|| other.font.clone()),
+            weight: self.weight.or(other.weight),
+            italic: self.italic.or(other.italic),
+            fallback: self.fallback.or_else(|| other.fallback.clone()),
+            direction: self.direction.or_else(|| other.direction.clone()),
+            language: self.language.or_else(|| other.language.clone()),
+            text_align: self.text_align.or_else(|| other.text_align.clone()),
+            size: self.size.or(other.size),
+            color: self.color.or_else(|| other.color.clone()),
+            line_height: self.line_height.or(other.line_height)
+        }
+    }
+}
+
+/// A theme from `themes/<name>.json`: a palette of named colors that any color in a page
+/// can refer to, and named text styles components pick with `"style"`.
+/// The palette entries `text`, `secondary` and `background` replace the colors from the settings.
+#[derive(Debug, Default)]
+pub struct Theme {
+    palette: HashMap<String, Color>,
+    styles: HashMap<String, StyleDef>
+}
+
+impl Theme {
+    pub fn new(value: &Value) -> Result<Self> {
+        let mut palette = HashMap::new();
+        if let Some(colors) = value["palette"].as_object() {
+            for (name, color) in colors {
+                let color = color.as_str().ok_or_else(|| ScreenError::Config(format!("palette color {} is not a string", name)))?;
+                palette.insert(name.clone(), Color::parse(color)?);
+            }
+        }
+        let mut styles = HashMap::new();
+        if let Some(defs) = value["styles"].as_object() {
+            for (name, def) in defs {
+                styles.insert(name.clone(), StyleDef::new(def)?);
+            }
+        }
+        Ok(Theme { palette, styles })
+    }
+
+    pub fn palette_color(&self, name: &str) -> Option<Color> {
+        self.palette.get(name).cloned()
+    }
+
+    pub fn style(&self, name: &str) -> Option<&StyleDef> {
+        self.styles.get(name)
+    }
+}
+
+/// A text style with its font loaded and color resolved.
+#[derive(Clone)]
+pub struct TextStyle {
+    pub font: Font,
+    pub color: Color,
+    pub line_height: f32,
+    pub align: TextAlign
+}
+
+impl TextStyle {
+    pub fn text_box(&self, text: &str) -> TextBox {
+        TextBox::new(text, &self.font, &self.color).with_line_height(self.line_height).with_align(self.align)
+    }
+
+    pub fn span(&self, text: &str) -> Span {
+        Span { text: text.to_string(), font: self.font.clone(), color: self.color.clone() }
+    }
+}
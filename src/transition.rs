@@ -0,0 +1,106 @@
+use std::{thread::sleep, time::{Duration, Instant}};
+
+use serde_json::Value;
+
+use crate::{framebuffer::FrameBuffer, output::DisplaySink};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransitionKind {
+    Cut,
+    Crossfade,
+    SlideLeft,
+    SlideRight,
+    SlideUp,
+    Wipe
+}
+
+/// How a page replaces the one before it, configured per page as e.g.
+/// `"transition": {"type": "crossfade", "duration": 600, "fps": 25}`.
+#[derive(Debug, Clone)]
+pub struct Transition {
+    kind: TransitionKind,
+    duration: Duration,
+    fps: u32
+}
+
+impl Transition {
+    pub fn new(value: &Value) -> Self {
+        let kind = match value["type"].as_str() {
+            Some("crossfade") | Some("fade") => TransitionKind::Crossfade,
+            Some("slide-left") => TransitionKind::SlideLeft,
+            Some("slide-right") => TransitionKind::SlideRight,
+            Some("slide-up") => TransitionKind::SlideUp,
+            Some("wipe") => TransitionKind::Wipe,
+            _ => TransitionKind::Cut
+        };
+        let duration = Duration::from_millis(value["duration"].as_u64().unwrap_or(500));
+        let fps = value["fps"].as_u64().unwrap_or(25).max(1) as u32;
+        Transition { kind, duration, fps }
+    }
+
+    /// Shows the in-between frames going from `from` to `to`. The final frame, `to`
+    /// itself, is left for the caller to show.
+    pub fn run(&self, from: &FrameBuffer, to: &FrameBuffer, sink: &mut dyn DisplaySink) {
+        let frames = (self.duration.as_secs_f32() * self.fps as f32) as u32;
+        if self.kind == TransitionKind::Cut || frames < 2 {
+            return;
+        }
+        let frame_time = Duration::from_secs_f32(1.0 / self.fps as f32);
+        let mut blend = FrameBuffer::new(to.width(), to.height());
+        for i in 1..frames {
+            let start = Instant::now();
+            let t = ease(i as f32 / frames as f32);
+            self.compose(from, to, t, &mut blend);
+            sink.show(&blend);
+            if let Some(remaining) = frame_time.checked_sub(start.elapsed()) {
+                sleep(remaining);
+            }
+        }
+    }
+
+    fn compose(&self, from: &FrameBuffer, to: &FrameBuffer, t: f32, out: &mut FrameBuffer) {
+        let width = out.width() as usize;
+        let height = out.height() as usize;
+        let row = width * 4;
+        let (src_from, src_to) = (from.pixels(), to.pixels());
+        let dst = out.pixels_mut();
+        match self.kind {
+            TransitionKind::Crossfade => {
+                for ((d, a), b) in dst.iter_mut().zip(src_from.iter()).zip(src_to.iter()) {
+                    *d = (*a as f32 + (t * (*b as f32 - *a as f32))).round() as u8;
+                }
+            },
+            TransitionKind::SlideLeft | TransitionKind::SlideRight => {
+                let shift = ((t * width as f32) as usize).min(width) * 4;
+                for y in 0..height {
+                    let (d, a, b) = (&mut dst[y * row..(y + 1) * row], &src_from[y * row..(y + 1) * row], &src_to[y * row..(y + 1) * row]);
+                    if self.kind == TransitionKind::SlideLeft {
+                        d[..row - shift].copy_from_slice(&a[shift..]);
+                        d[row - shift..].copy_from_slice(&b[..shift]);
+                    } else {
+                        d[..shift].copy_from_slice(&b[row - shift..]);
+                        d[shift..].copy_from_slice(&a[..row - shift]);
+                    }
+                }
+            },
+            TransitionKind::SlideUp => {
+                let shift = ((t * height as f32) as usize).min(height) * row;
+                let len = height * row;
+                dst[..len - shift].copy_from_slice(&src_from[shift..len]);
+                dst[len - shift..len].copy_from_slice(&src_to[..shift]);
+            },
+            TransitionKind::Wipe => {
+                let edge = ((t * width as f32) as usize).min(width) * 4;
+                for y in 0..height {
+                    dst[y * row..y * row + edge].copy_from_slice(&src_to[y * row..y * row + edge]);
+                    dst[y * row + edge..(y + 1) * row].copy_from_slice(&src_from[y * row + edge..(y + 1) * row]);
+                }
+            },
+            TransitionKind::Cut => dst.copy_from_slice(src_to)
+        }
+    }
+}
+
+fn ease(t: f32) -> f32 {
+    t * t * (3.0 - (2.0 * t))
+}
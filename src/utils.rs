@@ -1,17 +1,32 @@
-use std::{fs::{self, File}, io::{BufReader, Cursor, Read}};
+use std::{collections::HashMap, fs::{self, File}, io::{BufReader, Cursor, Read}, sync::OnceLock, time::Duration};
 use std::hash::{DefaultHasher, Hash, Hasher};
-use chrono::Weekday;
+use chrono::NaiveTime;
 use image::{DynamicImage, ImageReader};
+use reqwest::blocking::{Client, Response};
 use serde_json::Value;
 
+use crate::error::{Result, ScreenError};
 
+const IMAGE_TIMEOUT: Duration = Duration::from_secs(10);
+static CACHE_DIR: OnceLock<String> = OnceLock::new();
 
-pub fn list_folder_configs(folder: &str) -> Vec<String> {
+/// Sets where downloaded images are kept, `cache` if never called.
+pub fn set_cache_dir(dir: &str) {
+    if let Err(e) = fs::create_dir_all(dir) {
+        log::warn!("Cannot create image cache {}: {}", dir, e);
+    }
+    let _ = CACHE_DIR.set(dir.to_string());
+}
+
+
+pub fn list_folder_configs(folder: &str) -> Result<Vec<String>> {
     let mut ret: Vec<String> = vec![];
-    let dir = fs::read_dir(folder).unwrap();
-    for dir_entry in dir {
-        let path_buf = dir_entry.unwrap().path();
-        let path = path_buf.as_os_str().to_str().unwrap();
+    let dir = fs::read_dir(folder).map_err(|e| ScreenError::io(folder, e))?;
+    for dir_entry in dir.flatten() {
+        let path_buf = dir_entry.path();
+        let Some(path) = path_buf.as_os_str().to_str() else {
+            continue;
+        };
         if path.ends_with(".json") {
             let s_pos = path.rfind("/").unwrap_or(0) + 1;
             let e_pos = path.len() - 5;
@@ -19,89 +34,133 @@ pub fn list_folder_configs(folder: &str) -> Vec<String> {
             ret.push(name.to_string());
         }
     };
-    ret
+    Ok(ret)
 }
 
-pub fn load_config(folder: &str, name: &str) -> Value {
+pub fn load_config(folder: &str, name: &str) -> Result<Value> {
     let path = format!("{}/{}.json", folder, name);
-    let file = File::open(path).unwrap();
+    let file = File::open(&path).map_err(|e| ScreenError::io(&path, e))?;
     let reader = BufReader::new(file);
-    let config: Value = serde_json::from_reader(reader).unwrap();
-    config
+    serde_json::from_reader(reader).map_err(|e| ScreenError::json(&path, e))
+}
+
+/// A folder of JSON configs. Once `keep_last_good` is on, `get` returns what was last
+/// passed to `keep` instead of reading the file, so a broken edit can be held back.
+pub struct ConfigFolder {
+    folder: String,
+    last_good: Option<HashMap<String, Value>>
+}
+
+impl ConfigFolder {
+    pub fn new(folder: &str) -> Self {
+        ConfigFolder { folder: folder.to_string(), last_good: None }
+    }
+
+    pub fn list(&self) -> Result<Vec<String>> {
+        list_folder_configs(&self.folder)
+    }
+
+    pub fn get(&self, name: &str) -> Result<Value> {
+        match self.last_good.as_ref().and_then(|configs| configs.get(name)) {
+            Some(config) => Ok(config.clone()),
+            None => load_config(&self.folder, name)
+        }
+    }
+
+    pub fn keep_last_good(&mut self) {
+        self.last_good.get_or_insert_with(HashMap::new);
+    }
+
+    /// Reads a config from disk, `None` if the file is gone.
+    pub fn read(&self, name: &str) -> Result<Option<Value>> {
+        let path = format!("{}/{}.json", self.folder, name);
+        if !fs::exists(&path).unwrap_or(false) {
+            return Ok(None);
+        }
+        load_config(&self.folder, name).map(Some)
+    }
+
+    pub fn keep(&mut self, name: &str, config: Value) {
+        if let Some(configs) = self.last_good.as_mut() {
+            configs.insert(name.to_string(), config);
+        }
+    }
+
+    pub fn forget(&mut self, name: &str) {
+        if let Some(configs) = self.last_good.as_mut() {
+            configs.remove(name);
+        }
+    }
+}
+
+/// Fetches `url`, giving up after `timeout`. Non-success statuses are errors.
+pub fn http_get(url: &str, timeout: Duration) -> Result<Response> {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    let client = CLIENT.get_or_init(Client::new);
+    client.get(url).timeout(timeout).send()
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| ScreenError::http(url, e))
 }
 
-pub fn get_image(uri: &str) -> Option<DynamicImage> {
+pub fn get_image(uri: &str) -> Result<DynamicImage> {
     let image_bytes = if uri.starts_with("http://") || uri.starts_with("https://") {
         let uri_hash = calculate_hash(&uri.to_string());
         let uri_str_hash = format!("{:x}", uri_hash);
-        let cache_path = format!("cache/{}", uri_str_hash);
-        if fs::exists(&cache_path).unwrap() {
-            match fs::read(&cache_path) {
-                Ok(res) => Some(res),
-                Err(_) => None,
-            }
-        } else {
-            match reqwest::blocking::get(uri) {
-                Ok(mut res) => {
-                    let mut bytes: Vec<u8> = vec![];
-                    let _ = res.read_to_end(&mut bytes);
-                    let _ = fs::write(&cache_path, &bytes);
-                    Some(bytes)
-                },
-                Err(_) => None,
+        let cache_dir = CACHE_DIR.get().map(String::as_str).unwrap_or("cache");
+        let cache_path = format!("{}/{}", cache_dir, uri_str_hash);
+        match fs::read(&cache_path) {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                let mut res = http_get(uri, IMAGE_TIMEOUT)?;
+                let mut bytes: Vec<u8> = vec![];
+                res.read_to_end(&mut bytes).map_err(|e| ScreenError::http(uri, e))?;
+                let _ = fs::write(&cache_path, &bytes);
+                bytes
             }
         }
-    } else if uri.starts_with("file://") {
-        let path = &uri[7..];
-        match fs::read(path) {
-            Ok(res) => Some(res),
-            Err(_) => None,
-        }
+    } else if let Some(path) = uri.strip_prefix("file://") {
+        fs::read(path).map_err(|e| ScreenError::io(path, e))?
     } else {
-        None
+        return Err(ScreenError::image(uri, "unsupported uri"));
     };
 
-    match image_bytes {
-        Some(bytes) => {
-            let image = ImageReader::new(Cursor::new(bytes)).with_guessed_format().unwrap().decode().unwrap();
-            Some(image)
-        },
-        None => {
-            None
-        }
-    }
+    ImageReader::new(Cursor::new(image_bytes)).with_guessed_format()
+        .map_err(|e| ScreenError::image(uri, e))?
+        .decode()
+        .map_err(|e| ScreenError::image(uri, e))
 }
 
-pub fn get_month_name(m: u32) -> String {
-    (match m {
-        1 =>"January",
-        2 => "February",
-        3 => "March",
-        4 => "April",
-        5 => "May",
-        6 => "June",
-        7 => "July",
-        8 => "August",
-        9 => "September",
-        10 => "October",
-        11 => "November",
-        12 => "December",
-        _ => "Unknown"
-    }).to_string()
+pub fn open_image(path: &str) -> Result<DynamicImage> {
+    ImageReader::open(path).map_err(|e| ScreenError::io(path, e))?
+        .decode()
+        .map_err(|e| ScreenError::image(path, e))
 }
 
-pub fn get_weekday_name(wd: Weekday) -> String {
-    (match wd {
-        Weekday::Sun => "Sunday",
-        Weekday::Mon => "Monday",
-        Weekday::Tue => "Tuesday",
-        Weekday::Wed => "Wednesday",
-        Weekday::Thu => "Thursday",
-        Weekday::Fri => "Friday",
-        Weekday::Sat => "Saturday",
-    }).to_string()
+/// A daily time window written as `"HH:MM-HH:MM"`, which may run past midnight.
+#[derive(Debug, Clone)]
+pub struct TimeRange {
+    from: NaiveTime,
+    to: NaiveTime
 }
 
+impl TimeRange {
+    pub fn parse(range: &str) -> Result<Self> {
+        let invalid = || ScreenError::Config(format!("invalid time range \"{}\", expected HH:MM-HH:MM", range));
+        let (from, to) = range.split_once('-').ok_or_else(invalid)?;
+        let from = NaiveTime::parse_from_str(from.trim(), "%H:%M").map_err(|_| invalid())?;
+        let to = NaiveTime::parse_from_str(to.trim(), "%H:%M").map_err(|_| invalid())?;
+        Ok(TimeRange { from, to })
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.from <= self.to {
+            time >= self.from && time < self.to
+        } else {
+            // A range like 22:00-06:00 runs past midnight
+            time >= self.from || time < self.to
+        }
+    }
+}
 
 fn calculate_hash<T: Hash>(t: &T) -> u64 {
     let mut s = DefaultHasher::new();
@@ -0,0 +1,63 @@
+use std::{collections::HashMap, io, sync::mpsc::Sender, thread};
+
+use inotify::{Inotify, WatchMask};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Page,
+    Provider,
+    Data
+}
+
+/// A JSON file in one of the watched folders was written, moved in or deleted.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub name: String
+}
+
+/// Watches the page, provider and data folders with inotify and sends a `Change`
+/// for every JSON file that changes, from a background thread.
+pub fn watch<T>(folders: &[(&str, ChangeKind)], sender: Sender<T>) -> io::Result<()>
+where T: From<Change> + Send + 'static {
+    let mut inotify = Inotify::init()?;
+    let mask = WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::DELETE | WatchMask::MOVED_FROM;
+    let mut kinds = HashMap::new();
+    for (folder, kind) in folders {
+        match inotify.watches().add(folder, mask) {
+            Ok(wd) => { kinds.insert(wd, *kind); },
+            Err(e) => log::warn!("Cannot watch {}: {}", folder, e)
+        }
+    }
+    thread::Builder::new().name("watcher".to_string()).spawn(move || {
+        let mut buffer = [0; 4096];
+        loop {
+            let events = match inotify.read_events_blocking(&mut buffer) {
+                Ok(events) => events,
+                Err(e) => {
+                    log::error!("Stopped watching configs: {}", e);
+                    return;
+                }
+            };
+            // Editors often produce several events for one save, report each file once
+            let mut changes: Vec<Change> = vec![];
+            for event in events {
+                let (Some(kind), Some(file_name)) = (kinds.get(&event.wd), event.name.and_then(|n| n.to_str())) else {
+                    continue;
+                };
+                let Some(name) = file_name.strip_suffix(".json") else {
+                    continue;
+                };
+                if !changes.iter().any(|c| c.kind == *kind && c.name == name) {
+                    changes.push(Change { kind: *kind, name: name.to_string() });
+                }
+            }
+            for change in changes {
+                if sender.send(T::from(change)).is_err() {
+                    return;
+                }
+            }
+        }
+    })?;
+    Ok(())
+}